@@ -0,0 +1,103 @@
+#define_import_path bevy_post_process_2_input_image::composite
+
+// The default compositing of the background dimensions, replace this file with
+// `PostProcessPlugin::composite_shader` to composite them your own way.
+//
+// A replacement must use the same import path and define:
+//
+//     fn composite_offset(slot: u32, uv: vec2<f32>, time: f32) -> vec2<f32>
+//     fn composite_background(colors: array<vec4<f32>, 4>, depths: array<f32, 4>, count: u32, depth_compositing: bool) -> vec4<f32>
+//
+// This module is resolved on its own, it can't use the bindings or the functions of `post_processing.wgsl`,
+// which samples the background dimensions and passes them in:
+// - `composite_offset` returns the offset added to the uv at which the dimension in `slot` is sampled,
+//   for the screen `uv`, `time` is the time of the animated effects,
+// - `composite_background` returns the straight alpha color of the background, from 0 to 1,
+//   `colors[slot]` and `depths[slot]` are the color and the depth of the background dimension in `slot`,
+//   slot 0 is the selected dimension and the next ones follow it, up to `count` bound dimensions,
+//   the others are transparent black. `depths` are only read with `depth_compositing`, from the dimensions
+//   rendered with depth, the others are at the far plane: bevy uses a reversed depth, 1 is near and 0 is far.
+
+fn composite_offset(slot: u32, uv: vec2<f32>, time: f32) -> vec2<f32> {
+    if slot != 0u {
+        return vec2(0.0);
+    }
+    let noise = simplexNoise2((uv * 15.5) + time * 0.5);
+    return vec2(noise * 0.005 + sin(time) * 0.01);
+}
+
+// Composites the background dimensions, the result is then transitioned and drawn under the foreground.
+fn composite_background(colors: array<vec4<f32>, 4>, depths: array<f32, 4>, count: u32, depth_compositing: bool) -> vec4<f32> {
+    let c1 = colors[0];
+    let c2 = colors[1];
+    if depth_compositing {
+        // The nearest fragment wins.
+        if depths[1] > depths[0] {
+            return c2;
+        }
+        return c1;
+    }
+    if c2.r > 0.0 || c2.g > 0.0 || c2.b > 0.0 {
+        return c2;
+    }
+    return mix(c1, c2, 0.9);
+}
+
+fn mod289(x: vec2<f32>) -> vec2<f32> {
+    return x - floor(x * (1. / 289.)) * 289.;
+}
+
+fn mod289_3(x: vec3<f32>) -> vec3<f32> {
+    return x - floor(x * (1. / 289.)) * 289.;
+}
+
+fn permute3(x: vec3<f32>) -> vec3<f32> {
+    return mod289_3(((x * 34.) + 1.) * x);
+}
+
+//  MIT License. © Ian McEwan, Stefan Gustavson, Munrocket
+fn simplexNoise2(v: vec2<f32>) -> f32 {
+    let C = vec4(
+        0.211324865405187, // (3.0-sqrt(3.0))/6.0
+        0.366025403784439, // 0.5*(sqrt(3.0)-1.0)
+        -0.577350269189626, // -1.0 + 2.0 * C.x
+        0.024390243902439 // 1.0 / 41.0
+    );
+
+    // First corner
+    var i = floor(v + dot(v, C.yy));
+    let x0 = v - i + dot(i, C.xx);
+
+    // Other corners
+    var i1 = select(vec2(0., 1.), vec2(1., 0.), x0.x > x0.y);
+
+    // x0 = x0 - 0.0 + 0.0 * C.xx ;
+    // x1 = x0 - i1 + 1.0 * C.xx ;
+    // x2 = x0 - 1.0 + 2.0 * C.xx ;
+    var x12 = x0.xyxy + C.xxzz;
+    x12.x = x12.x - i1.x;
+    x12.y = x12.y - i1.y;
+
+    // Permutations
+    i = mod289(i); // Avoid truncation effects in permutation
+
+    var p = permute3(permute3(i.y + vec3(0., i1.y, 1.)) + i.x + vec3(0., i1.x, 1.));
+    var m = max(0.5 - vec3(dot(x0, x0), dot(x12.xy, x12.xy), dot(x12.zw, x12.zw)), vec3(0.));
+    m *= m;
+    m *= m;
+
+    // Gradients: 41 points uniformly over a line, mapped onto a diamond.
+    // The ring size 17*17 = 289 is close to a multiple of 41 (41*7 = 287)
+    let x = 2. * fract(p * C.www) - 1.;
+    let h = abs(x) - 0.5;
+    let ox = floor(x + 0.5);
+    let a0 = x - ox;
+
+    // Normalize gradients implicitly by scaling m
+    // Approximation of: m *= inversesqrt( a0*a0 + h*h );
+    m *= 1.79284291400159 - 0.85373472095314 * (a0 * a0 + h * h);
+
+    // Compute final noise value at P
+    let g = vec3(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
+    return 130. * dot(m, g);
+}
@@ -0,0 +1,31 @@
+// Downscales a dimension texture before the post process, see `PreDownscale`.
+//
+// Each texel of the destination is the average of the source texels it covers,
+// the post process then samples the destination in place of the dimension texture.
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var destination: texture_storage_2d<rgba16float, write>;
+
+// Must match `WORKGROUP_SIZE` in `downscale.rs`.
+@compute @workgroup_size(8, 8, 1)
+fn downscale(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = vec2<i32>(textureDimensions(destination));
+    let texel = vec2<i32>(id.xy);
+    if texel.x >= size.x || texel.y >= size.y {
+        return;
+    }
+    let source_size = vec2<i32>(textureDimensions(source));
+    // The sizes are rounded down, the last texels of the source may be left out.
+    let factor = max(source_size / size, vec2<i32>(1));
+    let origin = texel * factor;
+
+    var sum = vec4<f32>(0.0);
+    for (var y = 0; y < factor.y; y += 1) {
+        for (var x = 0; x < factor.x; x += 1) {
+            sum += textureLoad(source, min(origin + vec2<i32>(x, y), source_size - 1), 0);
+        }
+    }
+    textureStore(destination, texel, sum / f32(factor.x * factor.y));
+}
@@ -0,0 +1,30 @@
+// Blends the previous frames over the composite, see `PostProcessSettings::feedback`.
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+struct FeedbackUniform {
+    // How much of the previous frames is kept, from 0 to 1.
+    feedback: f32,
+}
+
+// The composite of this frame, of the size of the view target.
+@group(0) @binding(0) var composite: texture_2d<f32>;
+// The output of the previous frame.
+@group(0) @binding(1) var history: texture_2d<f32>;
+@group(0) @binding(2) var<uniform> settings: FeedbackUniform;
+
+struct FeedbackOutput {
+    // The view target.
+    @location(0) destination: vec4<f32>,
+    // What the next frame reads as `history`.
+    @location(1) history: vec4<f32>,
+}
+
+@fragment
+fn feedback(in: FullscreenVertexOutput) -> FeedbackOutput {
+    // Texel for texel, the viewport is set on the pass like for the composite.
+    let position = vec2<i32>(in.position.xy);
+    let current = textureLoad(composite, position, 0);
+    let previous = textureLoad(history, position, 0);
+    let color = mix(current, previous, settings.feedback);
+    return FeedbackOutput(color, color);
+}
@@ -0,0 +1,13 @@
+// Generates a mip level of a dimension texture from the previous one, see `create_mipmapped_render_target`.
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+// The previous mip level.
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+@fragment
+fn downsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // The level is half the size of the previous one:
+    // the linear sampler averages the 2x2 texels under each texel.
+    return textureSample(source, source_sampler, in.uv);
+}
@@ -1,4 +1,6 @@
+#ifdef GLOBALS
 #import bevy_render::globals
+#endif
 
 // Since post processing is a fullscreen effect, we use the fullscreen vertex shader provided by bevy.
 // This will import a vertex shader that renders a single fullscreen triangle.
@@ -19,78 +21,507 @@
 //
 // You don't need to worry about this too much since bevy will compute the correct UVs for you.
 #import bevy_core_pipeline::fullscreen_vertex_shader
+// Defines `composite_offset` and `composite_background`, which can be replaced,
+// see `PostProcessPlugin::composite_shader`.
+#import bevy_post_process_2_input_image::composite
 
+// Number of dimensions bound per group, see `PostProcessPlugin::max_textures`.
+const TEXTURE_COUNT: u32 = #{TEXTURE_COUNT}u;
+// Index of the first foreground texture.
+const FOREGROUND: u32 = TEXTURE_COUNT;
+const PI: f32 = 3.14159265;
+
+#ifdef GLOBALS
 @group(0) @binding(0) var<uniform> globals: Globals;
+#endif
+// The dimension textures are split in two groups of `TEXTURE_COUNT` textures:
+// - `textures[0..TEXTURE_COUNT]` is the background group (`Dimensions`),
+// - `textures[FOREGROUND..]` is the foreground group (`ForegroundDimensions`).
+// Within a group, the selected dimension comes first. Unused slots hold a transparent black texture,
+// as well as the slots of dimensions sharing the texture of a previous slot, see `texture_index`.
+// With `DimensionStorage::TextureArray`, the slots are the layers of a single array texture.
+// Without binding array support (WebGL2), each slot has its own binding.
+#ifdef DIMENSION_TEXTURE_ARRAY
+@group(0) @binding(1) var textures: texture_2d_array<f32>;
+#else
+#ifdef INDIVIDUAL_TEXTURE_BINDINGS
+// `TEXTURE_COUNT` is then 2, see `INDIVIDUAL_TEXTURE_COUNT`.
+@group(0) @binding(10) var texture_0: texture_2d<f32>;
+@group(0) @binding(11) var texture_1: texture_2d<f32>;
+@group(0) @binding(12) var texture_2: texture_2d<f32>;
+@group(0) @binding(13) var texture_3: texture_2d<f32>;
+#else
 @group(0) @binding(1) var textures: binding_array<texture_2d<f32>>;
+#endif
+#endif
 @group(0) @binding(2) var nearest_sampler: sampler;
 
-fn mod289(x: vec2<f32>) -> vec2<f32> {
-    return x - floor(x * (1. / 289.)) * 289.;
+// Per-dimension settings, indexed like `textures`.
+struct DimensionParams {
+    uv_offset: vec2<f32>,
+    uv_scale: vec2<f32>,
+    alpha: f32,
+    desaturate: f32,
+    // Index in `textures` of the texture of this dimension,
+    // dimensions sharing the same image share the same texture.
+    texture_index: u32,
+    // In texels, 0 disables the blur.
+    blur_radius: f32,
+    // Applied around the center of the dimension, before `uv_scale` and `uv_offset`.
+    uv_matrix: mat3x3<f32>,
+    // 1 when the texture holds premultiplied alpha colors.
+    premultiplied: u32,
+    // 1 to flip the dimension on this axis, before the other UV transforms.
+    flip_x: u32,
+    flip_y: u32,
+    // The mip level the texture is sampled at.
+    lod_bias: f32,
 }
+// Must match `BOUND_TEXTURE_COUNT`, only the first `2 * TEXTURE_COUNT` are used.
+@group(0) @binding(3) var<uniform> params: array<DimensionParams, 8>;
 
-fn mod289_3(x: vec3<f32>) -> vec3<f32> {
-    return x - floor(x * (1. / 289.)) * 289.;
+// A dimension the transition goes away from.
+struct TransitionSource {
+    // Slot in `textures` of the dimension.
+    slot: u32,
+    // Progress of the transition away from the dimension.
+    progress: f32,
+    padding_1: u32,
+    padding_2: u32,
 }
 
-fn permute3(x: vec3<f32>) -> vec3<f32> {
-    return mod289_3(((x * 34.) + 1.) * x);
+struct PostProcessUniform {
+    pixel_size: f32,
+    // Index of the selected dimension, `textures[0]` is always the selected one.
+    selected_index: u32,
+    // Number of background dimensions bound in `textures`.
+    dimension_count: u32,
+    // Index of the selected foreground dimension, `textures[FOREGROUND]` is always the selected one.
+    foreground_selected_index: u32,
+    // Number of foreground dimensions bound in `textures`.
+    foreground_dimension_count: u32,
+    // Whether the background dimensions are composited by depth.
+    depth_compositing: u32,
+    vignette_strength: f32,
+    vignette_radius: f32,
+    aberration_strength: f32,
+    scanline_intensity: f32,
+    curvature: f32,
+    // Number of `transition_sources` in use, 0 when no transition is running.
+    transition_source_count: u32,
+    transition_feather: f32,
+    // Whether `transition_mask` shapes the transition.
+    transition_masked: u32,
+    // Whether the transition fades through `transition_fade_color`, in two phases.
+    transition_fade: u32,
+    transition_fade_color: vec4<f32>,
+    // The `FlashOnSwitch` color, its alpha is the intensity of the flash.
+    flash_color: vec4<f32>,
+    // The dimensions transitioned from, oldest first. Must match `MAX_TEXTURE_COUNT`.
+    transition_sources: array<TransitionSource, 4>,
+    // The `TonemapMode`: 0 for none, 1 for Reinhard, 2 for ACES.
+    tonemap: u32,
+    // The columns of `CompositeLayout::Grid`, 0 to show the selected dimension.
+    grid_columns: u32,
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    // Fragments whose alpha is below it are discarded, the main pass then shows.
+    overlay_threshold: f32,
+    // In 8 bit sRGB steps, 0 disables the dithering.
+    dither_strength: f32,
+    // Multiplies `globals.time`, see `time`.
+    time_scale: f32,
+    // The `BlendSpace`: 0 for linear, 1 for sRGB.
+    blend_space: u32,
 }
+@group(0) @binding(4) var<uniform> settings: PostProcessUniform;
 
-//  MIT License. © Ian McEwan, Stefan Gustavson, Munrocket
-fn simplexNoise2(v: vec2<f32>) -> f32 {
-    let C = vec4(
-        0.211324865405187, // (3.0-sqrt(3.0))/6.0
-        0.366025403784439, // 0.5*(sqrt(3.0)-1.0)
-        -0.577350269189626, // -1.0 + 2.0 * C.x
-        0.024390243902439 // 1.0 / 41.0
-    );
+// The depth of each dimension, indexed like `textures`.
+// Dimensions without a depth texture are at the far plane.
+#ifdef INDIVIDUAL_TEXTURE_BINDINGS
+@group(0) @binding(20) var depth_texture_0: texture_depth_2d;
+@group(0) @binding(21) var depth_texture_1: texture_depth_2d;
+@group(0) @binding(22) var depth_texture_2: texture_depth_2d;
+@group(0) @binding(23) var depth_texture_3: texture_depth_2d;
+#else
+@group(0) @binding(5) var depth_textures: binding_array<texture_depth_2d>;
+#endif
+
+// The main pass of the view, which the composite is blended onto, see `CompositeBlend`.
+@group(0) @binding(6) var source: texture_2d<f32>;
+// A grayscale mask shaping the transition, see `DimensionTransition::mask`.
+@group(0) @binding(7) var transition_mask: texture_2d<f32>;
+
+// Reads the depth of the dimension at `index`, bevy uses a reversed depth: 1 is near, 0 is far.
+fn dimension_depth(index: u32, uv: vec2<f32>) -> f32 {
+#ifdef INDIVIDUAL_TEXTURE_BINDINGS
+    switch index {
+        case 0u: { return load_depth(depth_texture_0, uv); }
+        case 1u: { return load_depth(depth_texture_1, uv); }
+        case 2u: { return load_depth(depth_texture_2, uv); }
+        default: { return load_depth(depth_texture_3, uv); }
+    }
+#else
+    return load_depth(depth_textures[index], uv);
+#endif
+}
+
+fn load_depth(depth_texture: texture_depth_2d, uv: vec2<f32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let coords = clamp(vec2<i32>(uv * vec2<f32>(size)), vec2(0), size - 1);
+    return textureLoad(depth_texture, coords, 0);
+}
+
+// The time of the animated effects, in seconds: 0 pauses them.
+// Without `PostProcessPlugin::globals`, it is always 0.
+fn time() -> f32 {
+#ifdef GLOBALS
+    return globals.time * settings.time_scale;
+#else
+    return 0.0;
+#endif
+}
+
+// Snaps the UV to the center of its cell in a grid of `settings.pixel_size` screen pixels.
+fn pixelate(uv: vec2<f32>) -> vec2<f32> {
+    if settings.pixel_size <= 1.0 {
+        return uv;
+    }
+    // Size of a screen pixel in UV space, it doesn't depend on the viewport position.
+    let texel = vec2(dpdx(uv.x), dpdy(uv.y));
+    let cell_size = texel * settings.pixel_size;
+    return (floor(uv / cell_size) + 0.5) * cell_size;
+}
+
+// Applies the dimension UV transform: the flips, the matrix around the center, then `uv * scale + offset`.
+// UVs ending up outside of 0..1 are wrapped or clamped depending on the sampler address mode.
+fn dimension_uv(index: u32, uv: vec2<f32>) -> vec2<f32> {
+    let dimension = params[index];
+    let flipped = select(uv, 1.0 - uv, vec2(dimension.flip_x != 0u, dimension.flip_y != 0u));
+    let transformed = (dimension.uv_matrix * vec3(flipped - 0.5, 1.0)).xy + 0.5;
+    return transformed * dimension.uv_scale + dimension.uv_offset;
+}
+
+// Rec. 709 luminance of a linear color.
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// Samples the texture in slot `texture_index` of `textures`, at the mip level `lod`.
+fn sample_texture(texture_index: u32, uv: vec2<f32>, lod: f32) -> vec4<f32> {
+#ifdef DIMENSION_TEXTURE_ARRAY
+    return textureSampleLevel(textures, nearest_sampler, uv, texture_index, lod);
+#else
+#ifdef INDIVIDUAL_TEXTURE_BINDINGS
+    switch texture_index {
+        case 0u: { return textureSampleLevel(texture_0, nearest_sampler, uv, lod); }
+        case 1u: { return textureSampleLevel(texture_1, nearest_sampler, uv, lod); }
+        case 2u: { return textureSampleLevel(texture_2, nearest_sampler, uv, lod); }
+        default: { return textureSampleLevel(texture_3, nearest_sampler, uv, lod); }
+    }
+#else
+    return textureSampleLevel(textures[texture_index], nearest_sampler, uv, lod);
+#endif
+#endif
+}
 
-    // First corner
-    var i = floor(v + dot(v, C.yy));
-    let x0 = v - i + dot(i, C.xx);
+// Size in texels of the texture in slot `texture_index` of `textures`.
+fn texture_size(texture_index: u32) -> vec2<f32> {
+#ifdef DIMENSION_TEXTURE_ARRAY
+    return vec2<f32>(textureDimensions(textures));
+#else
+#ifdef INDIVIDUAL_TEXTURE_BINDINGS
+    switch texture_index {
+        case 0u: { return vec2<f32>(textureDimensions(texture_0)); }
+        case 1u: { return vec2<f32>(textureDimensions(texture_1)); }
+        case 2u: { return vec2<f32>(textureDimensions(texture_2)); }
+        default: { return vec2<f32>(textureDimensions(texture_3)); }
+    }
+#else
+    return vec2<f32>(textureDimensions(textures[texture_index]));
+#endif
+#endif
+}
+
+// Samples a texture with a 3x3 gaussian kernel, its samples `radius` texels apart.
+// The number of samples is fixed, the radius is clamped on the CPU side.
+fn sample_blurred(texture_index: u32, uv: vec2<f32>, radius: f32, lod: f32) -> vec4<f32> {
+    let step = radius / texture_size(texture_index);
+    var color = vec4(0.0);
+    for (var x = -1; x <= 1; x += 1) {
+        for (var y = -1; y <= 1; y += 1) {
+            // 1 2 1 weights on both axes, they sum to 16.
+            let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+            let offset = vec2(f32(x), f32(y)) * step;
+            color += sample_texture(texture_index, uv + offset, lod) * weight;
+        }
+    }
+    return color;
+}
 
-    // Other corners
-    var i1 = select(vec2(0., 1.), vec2(1., 0.), x0.x > x0.y);
+// Samples the dimension texture at `index` with an already transformed UV,
+// applying the dimension desaturation and alpha.
+// Colors use straight alpha, the output is alpha blended:
+// premultiplied dimensions are converted to straight alpha first.
+fn sample_dimension(index: u32, uv: vec2<f32>) -> vec4<f32> {
+    let dimension = params[index];
+    var color: vec4<f32>;
+    if dimension.blur_radius > 0.0 {
+        color = sample_blurred(dimension.texture_index, uv, dimension.blur_radius, dimension.lod_bias);
+    } else {
+        color = sample_texture(dimension.texture_index, uv, dimension.lod_bias);
+    }
+    if dimension.premultiplied != 0u && color.a > 0.0 {
+        color = vec4(color.rgb / color.a, color.a);
+    }
+    color = vec4(mix(color.rgb, vec3(luminance(color.rgb)), dimension.desaturate), color.a);
+    color.a *= dimension.alpha;
+    return color;
+}
 
-    // x0 = x0 - 0.0 + 0.0 * C.xx ;
-    // x1 = x0 - i1 + 1.0 * C.xx ;
-    // x2 = x0 - 1.0 + 2.0 * C.xx ;
-    var x12 = x0.xyxy + C.xxzz;
-    x12.x = x12.x - i1.x;
-    x12.y = x12.y - i1.y;
+// Maps the HDR color to the displayable range, following `settings.tonemap`.
+fn tonemap(color: vec4<f32>) -> vec4<f32> {
+    switch settings.tonemap {
+        case 1u: {
+            return vec4(color.rgb / (1.0 + color.rgb), color.a);
+        }
+        case 2u: {
+            // Krzysztof Narkowicz's fit of the ACES filmic curve.
+            let x = color.rgb;
+            let mapped = (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+            return vec4(saturate(mapped), color.a);
+        }
+        default: {
+            return color;
+        }
+    }
+}
 
-    // Permutations
-    i = mod289(i); // Avoid truncation effects in permutation
+// Applies the brightness, contrast and gamma of the settings, in linear space.
+fn color_grade(color: vec4<f32>) -> vec4<f32> {
+    // Linear middle gray, the contrast pivot.
+    let middle_gray = 0.18;
+    var rgb = color.rgb * settings.brightness;
+    rgb = max((rgb - middle_gray) * settings.contrast + middle_gray, vec3(0.0));
+    rgb = pow(rgb, vec3(1.0 / max(settings.gamma, 0.0001)));
+    return vec4(rgb, color.a);
+}
 
-    var p = permute3(permute3(i.y + vec3(0., i1.y, 1.)) + i.x + vec3(0., i1.x, 1.));
-    var m = max(0.5 - vec3(dot(x0, x0), dot(x12.xy, x12.xy), dot(x12.zw, x12.zw)), vec3(0.));
-    m *= m;
-    m *= m;
+// Offsets the color by a 4x4 Bayer matrix threshold of the screen pixel,
+// `settings.dither_strength` 8 bit steps wide, to break the banding of gradients.
+// The steps are those of the sRGB view target: the offset is added to the encoded color,
+// in linear space it would be too small to matter in the lights and too large in the shadows.
+fn dither(color: vec4<f32>, position: vec2<f32>) -> vec4<f32> {
+    if settings.dither_strength <= 0.0 {
+        return color;
+    }
+    var bayer = array<f32, 16>(
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0,
+    );
+    let pixel = vec2<u32>(position) % 4u;
+    let threshold = (bayer[pixel.y * 4u + pixel.x] + 0.5) / 16.0 - 0.5;
+    let encoded = linear_to_srgb(color.rgb) + threshold * settings.dither_strength / 255.0;
+    return vec4(srgb_to_linear(encoded), color.a);
+}
 
-    // Gradients: 41 points uniformly over a line, mapped onto a diamond.
-    // The ring size 17*17 = 289 is close to a multiple of 41 (41*7 = 287)
-    let x = 2. * fract(p * C.www) - 1.;
-    let h = abs(x) - 0.5;
-    let ox = floor(x + 0.5);
-    let a0 = x - ox;
+// Darkens the color toward the screen corners, starting at `settings.vignette_radius` from the center.
+fn vignette(color: vec4<f32>, uv: vec2<f32>) -> vec4<f32> {
+    if settings.vignette_strength <= 0.0 {
+        return color;
+    }
+    // 0 at the center, 1 at the middle of the edges, sqrt(2) at the corners.
+    let distance = length(uv - 0.5) * 2.0;
+    let darkening = smoothstep(settings.vignette_radius, 1.4142135, distance) * settings.vignette_strength;
+    return vec4(color.rgb * (1.0 - darkening), color.a);
+}
 
-    // Normalize gradients implicitly by scaling m
-    // Approximation of: m *= inversesqrt( a0*a0 + h*h );
-    m *= 1.79284291400159 - 0.85373472095314 * (a0 * a0 + h * h);
+// Bends the UV like a CRT screen, the corners moving away by `settings.curvature`.
+fn barrel(uv: vec2<f32>) -> vec2<f32> {
+    if settings.curvature <= 0.0 {
+        return uv;
+    }
+    // -1..1, the corners are at a squared distance of 2 from the center.
+    let centered = uv * 2.0 - 1.0;
+    let bent = centered * (1.0 + settings.curvature * dot(centered, centered) * 0.5);
+    return bent * 0.5 + 0.5;
+}
 
-    // Compute final noise value at P
-    let g = vec3(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
-    return 130. * dot(m, g);
+// Darkens every other row of `row_height`, the rows scrolling down with time.
+fn scanlines(color: vec4<f32>, uv: vec2<f32>, row_height: f32) -> vec4<f32> {
+    if settings.scanline_intensity <= 0.0 {
+        return color;
+    }
+    let row = uv.y / row_height - time() * 10.0;
+    let darkening = (0.5 + 0.5 * cos(row * PI)) * settings.scanline_intensity;
+    return vec4(color.rgb * (1.0 - darkening), color.a);
 }
 
 @fragment
 fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
-    let noise = simplexNoise2((in.uv * 15.5) + globals.time * 0.5);
-    let c1 = textureSample(textures[0], nearest_sampler, in.uv + noise * 0.005 + sin(globals.time) * 0.01);// * 0.1;
-    var c2 = textureSample(textures[1], nearest_sampler, in.uv);
-    if c2.r > 0.0 || c2.g > 0.0 || c2.b > 0.0 {
-        return c2;
-    }
-    //c2.g = noise;
-    return mix(c1, c2, 0.9);
+    // Height of a screen pixel in UV space.
+    let row_height = abs(dpdy(in.uv.y));
+    let curved = barrel(in.uv);
+    let uv = pixelate(curved);
+    let color = dither(
+        scanlines(vignette(color_grade(tonemap(flash(chromatic_aberration(uv)))), curved), curved, row_height),
+        in.position.xy,
+    );
+    // Sampling must stay in uniform control flow, so what is bent outside of the screen is discarded last.
+    let outside = any(curved < vec2(0.0)) || any(curved > vec2(1.0));
+    if color.a < settings.overlay_threshold && !outside {
+        discard;
+    }
+    return select(color, vec4(0.0, 0.0, 0.0, 1.0), outside);
+}
+
+// Adds the `FlashOnSwitch` over the composite, transparent parts included.
+fn flash(color: vec4<f32>) -> vec4<f32> {
+    let intensity = settings.flash_color.a;
+    return vec4(color.rgb + settings.flash_color.rgb * intensity, max(color.a, intensity));
+}
+
+// Tiles the bound background dimensions in a square grid of `settings.grid_columns` cells per side,
+// in the order of `Dimensions::dimensions`. Cells without a dimension are transparent.
+fn grid(uv: vec2<f32>) -> vec4<f32> {
+    let cell = vec2<u32>(uv * f32(settings.grid_columns));
+    let cell_uv = fract(uv * f32(settings.grid_columns));
+    let index = cell.y * settings.grid_columns + cell.x;
+    var color = vec4(0.0);
+    // Every slot is sampled, so sampling stays in uniform control flow.
+    for (var slot = 0u; slot < TEXTURE_COUNT; slot += 1u) {
+        let sampled = sample_dimension(slot, dimension_uv(slot, cell_uv));
+        // The selected dimension is in slot 0, the next ones follow it.
+        let dimension = (settings.selected_index + slot) % max(settings.dimension_count, 1u);
+        if slot < settings.dimension_count && dimension == index {
+            color = sampled;
+        }
+    }
+    return color;
+}
+
+// Composites the dimensions at `uv`, with the red and blue channels
+// taken `settings.aberration_strength` away from the center, scaled by the distance to it.
+fn chromatic_aberration(uv: vec2<f32>) -> vec4<f32> {
+    let composited = composite(uv);
+    if settings.aberration_strength <= 0.0 {
+        return composited;
+    }
+    // 0 at the center, 1 at the middle of the edges.
+    let offset = (uv - 0.5) * 2.0 * settings.aberration_strength;
+    let red = composite(uv + offset);
+    let blue = composite(uv - offset);
+    return vec4(red.r, composited.g, blue.b, composited.a);
+}
+
+// Draws the selected foreground dimension over the background, using its alpha.
+fn composite(uv: vec2<f32>) -> vec4<f32> {
+    if settings.grid_columns > 0u {
+        return grid(uv);
+    }
+    let background = transition(uv);
+    let foreground = to_blend_space(sample_dimension(FOREGROUND, dimension_uv(FOREGROUND, uv)));
+    return from_blend_space(mix(background, foreground, foreground.a));
+}
+
+// Converts a linear color to the space the dimensions are mixed in, see `BlendSpace`.
+fn to_blend_space(color: vec4<f32>) -> vec4<f32> {
+    if settings.blend_space == 0u {
+        return color;
+    }
+    return vec4(linear_to_srgb(color.rgb), color.a);
+}
+
+// Converts a color mixed in the `BlendSpace` back to linear.
+fn from_blend_space(color: vec4<f32>) -> vec4<f32> {
+    if settings.blend_space == 0u {
+        return color;
+    }
+    return vec4(srgb_to_linear(color.rgb), color.a);
+}
+
+// The sRGB transfer function, negative values are clamped to 0.
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let rgb = max(color, vec3(0.0));
+    let curve = 1.055 * pow(rgb, vec3(1.0 / 2.4)) - 0.055;
+    return select(curve, rgb * 12.92, rgb <= vec3(0.0031308));
+}
+
+// The inverse of `linear_to_srgb`.
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let rgb = max(color, vec3(0.0));
+    let curve = pow((rgb + 0.055) / 1.055, vec3(2.4));
+    return select(curve, rgb / 12.92, rgb <= vec3(0.04045));
+}
+
+// Samples the bound background dimensions for `composite_background`, which can't reach the bindings:
+// it is an import module of its own, see `PostProcessPlugin::composite_shader`.
+fn background(uv: vec2<f32>) -> vec4<f32> {
+    // Like the empty slots, the slots past `TEXTURE_COUNT` are transparent black and at the far plane.
+    var colors: array<vec4<f32>, 4>;
+    var depths: array<f32, 4>;
+    let time = time();
+    let depth_compositing = settings.depth_compositing != 0u;
+    for (var slot = 0u; slot < TEXTURE_COUNT; slot += 1u) {
+        let dimension_uv = dimension_uv(slot, uv);
+        colors[slot] = sample_dimension(slot, dimension_uv + composite_offset(slot, uv, time));
+        if depth_compositing {
+            depths[slot] = dimension_depth(slot, dimension_uv);
+        }
+    }
+    return composite_background(colors, depths, settings.dimension_count, depth_compositing);
+}
+
+// Transitions from the dimensions in `settings.transition_sources` to the composited background.
+// The colors are mixed, and returned, in the `BlendSpace`.
+fn transition(uv: vec2<f32>) -> vec4<f32> {
+    let to = to_blend_space(background(uv));
+    let count = settings.transition_source_count;
+    if count == 0u {
+        return to;
+    }
+    // Each source is revealed over the previous ones following the progress of the transition before it.
+    var from = sample_transition_source(0u, uv);
+    for (var i = 1u; i < count; i += 1u) {
+        let progress = settings.transition_sources[i - 1u].progress;
+        from = mix(from, sample_transition_source(i, uv), transition_reveal(uv, progress));
+    }
+    let progress = settings.transition_sources[count - 1u].progress;
+    if settings.transition_fade == 0u {
+        return mix(from, to, transition_reveal(uv, progress));
+    }
+    // Fade through a color: the previous dimensions fade out to it during the first half,
+    // the new dimension fades in from it during the second half.
+    let color = to_blend_space(settings.transition_fade_color);
+    if progress < 0.5 {
+        return mix(from, color, transition_reveal(uv, progress * 2.0));
+    }
+    return mix(color, to, transition_reveal(uv, progress * 2.0 - 1.0));
+}
+
+fn sample_transition_source(i: u32, uv: vec2<f32>) -> vec4<f32> {
+    let index = settings.transition_sources[i].slot;
+    return to_blend_space(sample_dimension(index, dimension_uv(index, uv)));
+}
+
+// How much of the new dimension is shown at `uv`: the progress for a crossfade,
+// or the mask compared to the progress, darker parts of the mask being revealed first.
+fn transition_reveal(uv: vec2<f32>, progress: f32) -> f32 {
+    if settings.transition_masked == 0u {
+        return progress;
+    }
+    let mask = textureSample(transition_mask, nearest_sampler, uv).r;
+    let feather = max(settings.transition_feather, 0.0001);
+    // The threshold goes past both ends of the mask, so the feathered edge fully leaves it.
+    let threshold = progress * (1.0 + 2.0 * feather) - feather;
+    return 1.0 - smoothstep(threshold - feather, threshold + feather, mask);
+}
+
+// Copies the main pass to the destination texture, before the composite is blended onto it.
+@fragment
+fn copy_source(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    return textureLoad(source, vec2<i32>(in.position.xy), 0);
 }
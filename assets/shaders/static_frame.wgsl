@@ -0,0 +1,11 @@
+// Copies the composite kept from a previous frame, see `PostProcessSettings::skip_static_frames`.
+#import bevy_core_pipeline::fullscreen_vertex_shader
+
+// The kept composite, of the size of the view target.
+@group(0) @binding(0) var composite: texture_2d<f32>;
+
+@fragment
+fn copy_composite(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Texel for texel, the viewport is set on the pass like for the composite.
+    return textureLoad(composite, vec2<i32>(in.position.xy), 0);
+}
@@ -0,0 +1,41 @@
+//! Compares the extraction of 32 dimensions to the render world before and after only the bound
+//! dimensions were extracted: the whole `Dimensions` used to be cloned every frame.
+use bevy::{asset::HandleId, prelude::*};
+use bevy_post_process_2_input_image::{
+    extract_dimensions_bench, DimensionDef, Dimensions, ForegroundDimensions,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const DIMENSION_COUNT: usize = 32;
+
+fn dimensions() -> Dimensions {
+    (0..DIMENSION_COUNT).fold(Dimensions::default(), |dimensions, index| {
+        dimensions.add_dimension(DimensionDef {
+            name: Some(format!("dimension {index}")),
+            image: Handle::weak(HandleId::random::<Image>()),
+            ..default()
+        })
+    })
+}
+
+fn extract(c: &mut Criterion) {
+    let dimensions = dimensions();
+    let foreground = ForegroundDimensions(self::dimensions());
+
+    let mut group = c.benchmark_group("extract 32 dimensions");
+    group.bench_function("clone", |b| {
+        b.iter(|| {
+            (
+                black_box(&dimensions).clone(),
+                black_box(&foreground).clone(),
+            )
+        })
+    });
+    group.bench_function("bound only", |b| {
+        b.iter(|| extract_dimensions_bench(black_box(&dimensions), Some(black_box(&foreground))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, extract);
+criterion_main!(benches);
@@ -0,0 +1,376 @@
+//! Shows how to render to a texture. Useful for mirrors, UI, or exporting images.
+//!
+//! Run with `cargo run --example mirror`, or `cargo run --example mirror -- 4` for 4 dimensions
+//! (`DIMENSIONS=4` works too), each with a quad of its own color.
+//! Add `--features inspector` for the world inspector (Escape) and the dimensions overlay (F1),
+//! `--features hot_reload` to edit `post_processing.wgsl` while the example runs.
+
+#[cfg(feature = "inspector")]
+mod debug_overlay;
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig, input::common_conditions::input_just_pressed,
+    prelude::*, render::render_resource::Extent3d,
+};
+use bevy_post_process_2_input_image::{
+    apply_switch_dimension, auto_cycle_system, prelude::*, MAX_TEXTURE_COUNT,
+};
+
+fn main() {
+    let mut app = App::new();
+    app.register_type::<Rotate>()
+        .register_type::<Move>()
+        .add_plugins(DefaultPlugins)
+        // Bind every dimension, so each one can be selected.
+        .add_plugin(PostProcessPlugin {
+            max_textures: dimension_count(),
+            ..default()
+        })
+        .add_systems(Startup, setup)
+        .add_systems(Update, (rotator_system, move_system))
+        .add_systems(
+            Update,
+            (
+                switch_dimension.run_if(input_just_pressed(KeyCode::D)),
+                select_dimension,
+                toggle_auto_cycle.run_if(input_just_pressed(KeyCode::P)),
+            )
+                .before(auto_cycle_system),
+        )
+        .add_systems(
+            Update,
+            toggle_scaling_mode.run_if(input_just_pressed(KeyCode::N)),
+        )
+        .add_systems(
+            Update,
+            cycle_vignette.run_if(input_just_pressed(KeyCode::V)),
+        )
+        .add_systems(Update, toggle_crt.run_if(input_just_pressed(KeyCode::C)))
+        .add_systems(
+            Update,
+            toggle_post_process.run_if(input_just_pressed(KeyCode::Space)),
+        )
+        .add_systems(Update, toggle_mirror.run_if(input_just_pressed(KeyCode::M)))
+        .add_systems(Update, toggle_freeze.run_if(input_just_pressed(KeyCode::F)))
+        .add_systems(Update, peek_dimension)
+        .add_systems(
+            Update,
+            pick_on_click.run_if(input_just_pressed(MouseButton::Left)),
+        );
+
+    // egui is only pulled in by the `inspector` feature, the post process doesn't depend on it.
+    #[cfg(feature = "inspector")]
+    {
+        use bevy::input::common_conditions::input_toggle_active;
+        use bevy_inspector_egui::quick::WorldInspectorPlugin;
+        use debug_overlay::{debug_dimensions_overlay, toggle_debug_dimensions, DebugDimensions};
+
+        app.add_plugin(
+            WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::Escape)),
+        )
+        .init_resource::<DebugDimensions>()
+        .add_systems(
+            Update,
+            (
+                toggle_debug_dimensions.run_if(input_just_pressed(KeyCode::F1)),
+                debug_dimensions_overlay,
+            )
+                .chain()
+                .after(apply_switch_dimension),
+        );
+    }
+
+    app.run();
+}
+
+#[derive(Component, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+struct Rotate(f32);
+#[derive(Component, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+struct Move;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    mut layers: ResMut<DimensionLayers>,
+) {
+    let size = Extent3d {
+        width: 1280,
+        height: 720,
+        ..default()
+    };
+    // Dimension 1 renders at the window resolution, the next ones at half of it,
+    // press N to compare linear and nearest scaling.
+    let dimensions: Vec<_> = (0..dimension_count())
+        .map(|index| {
+            let builder = if index == 0 {
+                DimensionBuilder::new(size)
+            } else {
+                DimensionBuilder::new(Extent3d {
+                    width: size.width / 2,
+                    height: size.height / 2,
+                    ..default()
+                })
+                .resize_policy(ResizePolicy::Proportional(0.5))
+            };
+            builder
+                .build(&mut layers, &mut images, &mut commands)
+                .unwrap_or_else(|_| {
+                    panic!(
+                        "a render layer should be available for dimension {}",
+                        index + 1
+                    )
+                })
+        })
+        .collect();
+    // A transparent dimension, drawn over the others.
+    let overlay = DimensionBuilder::new(size)
+        .clear_color(ClearColorConfig::Custom(Color::NONE))
+        .build(&mut layers, &mut images, &mut commands)
+        .expect("a render layer should be available for the overlay dimension");
+    // The main pass camera, the only one running the post process.
+    let background = dimensions
+        .iter()
+        .fold(Dimensions::default(), |background, dimension| {
+            background.add_dimension(dimension.def.clone())
+        });
+    let cameras: Vec<_> = dimensions
+        .iter()
+        .map(|dimension| dimension.camera)
+        .chain([overlay.camera])
+        .collect();
+    commands
+        .spawn((
+            Camera2dBundle {
+                transform: Transform::from_xyz(0.0, 0.0, 15.0).looking_at(Vec3::ZERO, Vec3::Y),
+                ..default()
+            },
+            background,
+            ForegroundDimensions(Dimensions::default().add_dimension(overlay.def)),
+            // Crossfade between dimensions when switching.
+            DimensionTransition::new(0.5),
+            // Flash the screen white on each switch.
+            FlashOnSwitch::default(),
+            // Hold Q to peek at the next dimension.
+            PeekDimension {
+                crossfade: true,
+                ..default()
+            },
+            // Press P to start cycling through dimensions automatically.
+            AutoCycle {
+                paused: true,
+                ..AutoCycle::new(2.0)
+            },
+            PostProcessCamera,
+            PostProcessSettings::default(),
+            Move,
+        ))
+        .push_children(&cameras);
+
+    // A quad within each dimension, red in dimension 1 and blue in dimension 2.
+    let quad_size = Vec2::new(250f32, 250f32);
+    for (index, dimension) in dimensions.iter().enumerate() {
+        let color = match index {
+            0 => Color::RED,
+            1 => Color::BLUE,
+            // Spread the hues of the next ones with the golden angle, so they stay distinct.
+            _ => Color::hsl(index as f32 * 137.5 % 360.0, 0.8, 0.5),
+        };
+        let mesh = meshes.add(shape::Quad::new(quad_size).into());
+        let cube_material_handle = materials.add(ColorMaterial {
+            color,
+            texture: None,
+        });
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: mesh.into(),
+                material: cube_material_handle,
+                transform: Transform::default(),
+                global_transform: GlobalTransform::default(),
+                visibility: Visibility::default(),
+                computed_visibility: ComputedVisibility::default(),
+            },
+            Rotate(1.8f32 - 0.3 * index as f32),
+            dimension.render_layers,
+        ));
+    }
+
+    // The sprite within the overlay dimension, everything around it stays transparent.
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: Color::YELLOW,
+                custom_size: Some(Vec2::new(80f32, 80f32)),
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, 200.0, 0.0),
+            ..default()
+        },
+        Rotate(-2.0f32),
+        overlay.render_layers,
+    ));
+}
+
+/// The number of background dimensions, from the first argument or the `DIMENSIONS` environment variable,
+/// 2 by default, and at most the `MAX_TEXTURE_COUNT` dimensions the post process can bind.
+fn dimension_count() -> usize {
+    std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("DIMENSIONS").ok())
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(2)
+        .clamp(1, MAX_TEXTURE_COUNT)
+}
+
+/// Rotates the inner cube (first dimension)
+fn rotator_system(time: Res<Time>, mut query: Query<(&mut Transform, &Rotate)>) {
+    for (mut transform, rotate) in &mut query {
+        //transform.rotate_x(1.5 * time.delta_seconds());
+        transform.rotate_z(rotate.0 * time.delta_seconds());
+    }
+}
+fn move_system(time: Res<Time>, mut query: Query<(&mut Transform, &Move)>) {
+    for (mut transform, rotate) in &mut query {
+        //transform.rotate_x(1.5 * time.delta_seconds());
+        transform.translation.x = f32::sin(time.elapsed_seconds() * 5f32) * 300f32;
+    }
+}
+
+fn toggle_auto_cycle(mut auto_cycles: Query<&mut AutoCycle>) {
+    for mut auto_cycle in auto_cycles.iter_mut() {
+        auto_cycle.paused = !auto_cycle.paused;
+    }
+}
+
+/// Selects the next dimension of every `Dimensions`.
+fn switch_dimension(
+    dim: Query<Entity, With<Dimensions>>,
+    mut switch: EventWriter<SwitchDimension>,
+) {
+    for entity in dim.iter() {
+        switch.send(SwitchDimension {
+            entity,
+            direction: 1,
+        });
+    }
+}
+
+/// Selects the dimension N of every `Dimensions` with the number key N, from 1 to 9.
+///
+/// The index is clamped to the last dimension, and reached with a `SwitchDimension`
+/// so the `DimensionTransition` runs like for any switch.
+fn select_dimension(
+    keys: Res<Input<KeyCode>>,
+    dim: Query<(Entity, &Dimensions)>,
+    mut switch: EventWriter<SwitchDimension>,
+) {
+    const NUMBER_KEYS: [KeyCode; 9] = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    let Some(index) = NUMBER_KEYS.iter().position(|key| keys.just_pressed(*key)) else {
+        return;
+    };
+    for (entity, dimensions) in dim.iter() {
+        let last = dimensions.dimensions.len().saturating_sub(1);
+        let direction = index.min(last) as i32 - dimensions.selected as i32;
+        if direction != 0 {
+            switch.send(SwitchDimension { entity, direction });
+        }
+    }
+}
+
+/// Shows the next dimension while Q is held, without changing the selection.
+fn peek_dimension(keys: Res<Input<KeyCode>>, mut peeks: Query<(&Dimensions, &mut PeekDimension)>) {
+    for (dimensions, mut peek) in peeks.iter_mut() {
+        if keys.just_pressed(KeyCode::Q) {
+            peek.index = next_selected(dimensions.selected, dimensions.dimensions.len() as u32);
+        }
+        let active = keys.pressed(KeyCode::Q);
+        if peek.active != active {
+            peek.active = active;
+        }
+    }
+}
+
+/// Switches between linear and nearest filtering of the dimension textures.
+///
+/// The difference shows when the dimension textures are smaller than the window,
+/// for example with a `ResizePolicy::Proportional` factor below 1.
+fn toggle_scaling_mode(mut settings: Query<&mut PostProcessSettings>) {
+    for mut settings in settings.iter_mut() {
+        settings.scaling_mode = match settings.scaling_mode {
+            ScalingMode::Linear => ScalingMode::Nearest,
+            ScalingMode::Nearest => ScalingMode::Linear,
+        };
+    }
+}
+
+/// Cycles the vignette strength between off, light and strong.
+fn cycle_vignette(mut settings: Query<&mut PostProcessSettings>) {
+    for mut settings in settings.iter_mut() {
+        settings.vignette_strength = match settings.vignette_strength {
+            s if s < 0.5 => 0.5,
+            s if s < 1.0 => 1.0,
+            _ => 0.0,
+        };
+    }
+}
+
+/// Toggles the CRT look: scrolling scanlines and a curved screen.
+fn toggle_crt(mut settings: Query<&mut PostProcessSettings>) {
+    for mut settings in settings.iter_mut() {
+        let enabled = settings.scanline_intensity > 0.0;
+        settings.scanline_intensity = if enabled { 0.0 } else { 0.3 };
+        settings.curvature = if enabled { 0.0 } else { 0.1 };
+    }
+}
+
+/// Flips the selected dimension horizontally, as seen in a mirror.
+fn toggle_mirror(mut dim: Query<&mut Dimensions>) {
+    for mut dimensions in dim.iter_mut() {
+        let selected = dimensions.displayed_index() as usize;
+        if let Some(dimension) = dimensions.dimensions.get_mut(selected) {
+            dimension.flip_x = !dimension.flip_x;
+        }
+    }
+}
+
+/// Freezes the dimensions on their current frame, switching between them keeps working.
+fn toggle_freeze(mut freeze: ResMut<FreezeDimensions>) {
+    freeze.0 = !freeze.0;
+}
+
+/// Logs the quad clicked in the displayed dimension, if any.
+fn pick_on_click(
+    windows: Query<&Window>,
+    cameras: Query<Entity, With<PostProcessCamera>>,
+    picking: DimensionPicking,
+) {
+    let Some(cursor) = windows.iter().next().and_then(Window::cursor_position) else {
+        return;
+    };
+    for camera in cameras.iter() {
+        if let Some(hit) = picking.pick(camera, cursor) {
+            info!(
+                "Clicked {:?} in dimension {} at {}",
+                hit.entity, hit.dimension, hit.world_position
+            );
+        }
+    }
+}
+
+/// Shows the main scene without the post process, to compare.
+fn toggle_post_process(mut enabled: ResMut<PostProcessEnabled>) {
+    enabled.0 = !enabled.0;
+}
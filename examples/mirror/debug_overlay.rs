@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+
+use bevy_post_process_2_input_image::Dimensions;
+
+/// Height of the dimension thumbnails, in egui points.
+const THUMBNAIL_HEIGHT: f32 = 90.0;
+
+/// Toggles an overlay listing the dimensions of every camera,
+/// with their selection and a thumbnail of each dimension texture.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugDimensions(pub bool);
+
+pub fn toggle_debug_dimensions(mut debug: ResMut<DebugDimensions>) {
+    debug.0 = !debug.0;
+}
+
+pub fn debug_dimensions_overlay(
+    debug: Res<DebugDimensions>,
+    mut contexts: EguiContexts,
+    images: Res<Assets<Image>>,
+    dim: Query<(Entity, &Dimensions)>,
+) {
+    if !debug.0 {
+        return;
+    }
+    // Register the textures first, `ctx_mut` borrows the contexts for the whole window.
+    let thumbnails: Vec<_> = dim
+        .iter()
+        .map(|(entity, dimensions)| {
+            let textures: Vec<_> = dimensions
+                .dimensions
+                .iter()
+                .map(|dimension| {
+                    let size = images.get(&dimension.image).map(|image| image.size());
+                    let layers = dimension
+                        .render_layers
+                        .map(|layers| layers.iter().collect::<Vec<_>>());
+                    (
+                        contexts.add_image(dimension.image.clone_weak()),
+                        size,
+                        layers,
+                    )
+                })
+                .collect();
+            (entity, dimensions, textures)
+        })
+        .collect();
+
+    egui::Window::new("Dimensions").show(contexts.ctx_mut(), |ui| {
+        for (entity, dimensions, textures) in thumbnails {
+            ui.label(format!(
+                "{entity:?}: dimension {} selected out of {}",
+                dimensions.selected,
+                dimensions.dimensions.len()
+            ));
+            ui.horizontal(|ui| {
+                for (index, (texture, size, layers)) in textures.into_iter().enumerate() {
+                    ui.vertical(|ui| {
+                        let marker = if index as u32 == dimensions.selected {
+                            ">"
+                        } else {
+                            " "
+                        };
+                        let layers =
+                            layers.map_or(String::new(), |layers| format!(", layers {layers:?}"));
+                        ui.label(format!("{marker} {index}{layers}"));
+                        match size {
+                            Some(size) if size.y > 0.0 => {
+                                let width = THUMBNAIL_HEIGHT * size.x / size.y;
+                                ui.image(texture, [width, THUMBNAIL_HEIGHT]);
+                            }
+                            _ => {
+                                ui.label("not loaded");
+                            }
+                        }
+                    });
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,411 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+        },
+        texture::TextureFormatPixelInfo,
+        view::RenderLayers,
+    },
+};
+
+use crate::{Dimensions, ForegroundDimensions, OutOfRenderLayers};
+
+/// Spawns a camera rendering `render_layers` to a new image of the given size.
+///
+/// Use `ClearColorConfig::Custom(Color::NONE)` for a transparent dimension, to composite it as an overlay.
+/// `ClearColorConfig::None` doesn't clear at all: the previous frames stay visible where nothing is drawn.
+///
+/// The camera must render before the camera compositing it, so its `order` must be lower,
+/// [`DIMENSION_CAMERA_ORDER`] is right below the default order of 0.
+///
+/// `projection` sets the zoom of the dimension, e.g. a `scale` above 1 for a zoomed-out overview
+/// of content shared with another dimension. `OrthographicProjection::default()` is the usual 2d projection.
+///
+/// The image stays single sampled whatever the `Msaa` resource: with `Msaa` on, the camera draws into
+/// a multisampled texture of the same size, resolved into the image before the post process samples it.
+/// That texture costs `samples` times the memory of the image, per camera. `Msaa` is global,
+/// so it can't be enabled for some dimensions only: turn it off for pixel art dimensions to stay crisp.
+pub fn create_camera(
+    size: Extent3d,
+    images: &mut Assets<Image>,
+    commands: &mut Commands<'_, '_>,
+    render_layers: RenderLayers,
+    clear_color: ClearColorConfig,
+    order: isize,
+    projection: OrthographicProjection,
+) -> (Handle<Image>, Entity) {
+    let image_handle = create_render_target(size, images);
+    let cam = spawn_camera_2d(
+        commands,
+        image_handle.clone(),
+        render_layers,
+        clear_color,
+        order,
+        projection,
+    );
+    (image_handle, cam)
+}
+
+/// Like [`create_camera`], but stacks several render layers into the same image, one camera per layer.
+///
+/// The layers are rendered in the order of `layer_order`, each one over the previous ones: only the first
+/// camera clears the image with `clear_color`. The last camera has the given `order`, the previous ones
+/// the orders right below it, so they all render before a camera compositing the image with a higher order.
+///
+/// Returns the image and the cameras, in the order of `layer_order`,
+/// or `OutOfRenderLayers` without spawning anything if a layer is not below [`RenderLayers::TOTAL_LAYERS`].
+pub fn create_stacked_cameras(
+    size: Extent3d,
+    images: &mut Assets<Image>,
+    commands: &mut Commands<'_, '_>,
+    layer_order: &[u8],
+    clear_color: ClearColorConfig,
+    order: isize,
+    projection: OrthographicProjection,
+) -> Result<(Handle<Image>, Vec<Entity>), OutOfRenderLayers> {
+    // `RenderLayers::layer` panics on them.
+    if layer_order
+        .iter()
+        .any(|&layer| layer as usize >= RenderLayers::TOTAL_LAYERS)
+    {
+        return Err(OutOfRenderLayers);
+    }
+    let image_handle = create_render_target(size, images);
+    let cameras = layer_order
+        .iter()
+        .enumerate()
+        .map(|(index, &layer)| {
+            spawn_camera_2d(
+                commands,
+                image_handle.clone(),
+                RenderLayers::layer(layer),
+                if index == 0 {
+                    clear_color.clone()
+                } else {
+                    ClearColorConfig::None
+                },
+                order - (layer_order.len() - 1 - index) as isize,
+                projection.clone(),
+            )
+        })
+        .collect();
+    Ok((image_handle, cameras))
+}
+
+fn spawn_camera_2d(
+    commands: &mut Commands<'_, '_>,
+    image_handle: Handle<Image>,
+    render_layers: RenderLayers,
+    clear_color: ClearColorConfig,
+    order: isize,
+    projection: OrthographicProjection,
+) -> Entity {
+    commands
+        .spawn((
+            Camera2dBundle {
+                projection,
+                camera_2d: Camera2d {
+                    clear_color,
+                    ..default()
+                },
+                camera: Camera {
+                    order,
+                    target: RenderTarget::Image(image_handle),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 15.0))
+                    .looking_at(Vec3::ZERO, Vec3::Y),
+                ..default()
+            },
+            render_layers,
+        ))
+        .id()
+}
+
+/// The order of the dimension cameras created by [`DimensionBuilder`](crate::DimensionBuilder) by default.
+pub const DIMENSION_CAMERA_ORDER: isize = -1;
+
+/// Like [`create_camera`], but spawns a 3d camera whose depth texture can be bound by the post process,
+/// for depth compositing (see `PostProcessSettings::depth_compositing`).
+///
+/// Its content must be 3d as well. The depth texture can't be multisampled, so `Msaa` must be `Off`.
+pub fn create_depth_camera(
+    size: Extent3d,
+    images: &mut Assets<Image>,
+    commands: &mut Commands<'_, '_>,
+    render_layers: RenderLayers,
+    clear_color: ClearColorConfig,
+    order: isize,
+) -> (Handle<Image>, Entity) {
+    let image_handle = create_render_target(size, images);
+
+    let cam = commands
+        .spawn((
+            Camera3dBundle {
+                camera_3d: Camera3d {
+                    clear_color,
+                    // The post process samples the depth texture.
+                    depth_texture_usages: (TextureUsages::RENDER_ATTACHMENT
+                        | TextureUsages::TEXTURE_BINDING)
+                        .into(),
+                    ..default()
+                },
+                camera: Camera {
+                    order,
+                    target: RenderTarget::Image(image_handle.clone()),
+                    ..default()
+                },
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 15.0))
+                    .looking_at(Vec3::ZERO, Vec3::Y),
+                ..default()
+            },
+            render_layers,
+        ))
+        .id();
+
+    (image_handle, cam)
+}
+
+/// Creates the image a dimension camera renders to.
+pub fn create_render_target(size: Extent3d, images: &mut Assets<Image>) -> Handle<Image> {
+    create_mipmapped_render_target(size, images, 1)
+}
+
+/// Like [`create_render_target`], with `mip_level_count` mip levels for `DimensionDef::lod_bias`.
+///
+/// The mips are generated by the post process every frame, before sampling the image:
+/// a pass per level, each reading the previous level, which costs about a third of a copy of the image.
+/// The count is clamped to the number of levels down to 1x1.
+pub fn create_mipmapped_render_target(
+    size: Extent3d,
+    images: &mut Assets<Image>,
+    mip_level_count: u32,
+) -> Handle<Image> {
+    // This is the texture that will be rendered to.
+    // Its format has an alpha channel, so transparent dimensions stay transparent.
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            // `COPY_SRC` allows reading it back, see `ReadbackRequest`.
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_SRC
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+
+    // fill image.data with zeroes
+    image.texture_descriptor.mip_level_count = mip_level_count;
+    resize_render_target(&mut image, size);
+
+    images.add(image)
+}
+
+/// Resizes a render target, its mip level count is clamped to the levels the new size has.
+///
+/// `Image::resize` only sizes the data of the first level, the data covers every level here.
+pub(crate) fn resize_render_target(image: &mut Image, size: Extent3d) {
+    let descriptor = &mut image.texture_descriptor;
+    let max_mip_level_count = 32 - size.width.max(size.height).max(1).leading_zeros();
+    descriptor.mip_level_count = descriptor.mip_level_count.clamp(1, max_mip_level_count);
+    if descriptor.mip_level_count == 1 {
+        image.resize(size);
+        return;
+    }
+    let texel_size = descriptor.format.pixel_size();
+    let data_len: usize = (0..descriptor.mip_level_count)
+        .map(|level| {
+            let width = (size.width >> level).max(1) as usize;
+            let height = (size.height >> level).max(1) as usize;
+            width * height * size.depth_or_array_layers as usize * texel_size
+        })
+        .sum();
+    descriptor.size = size;
+    image.data.clear();
+    image.data.resize(data_len, 0);
+}
+
+/// How often a dimension camera renders, add it to the camera of a mostly static dimension to save GPU time.
+///
+/// A camera which doesn't render keeps its image as is, and the post process keeps compositing it.
+/// Resizing the image clears it, so a resized image is rendered again whatever the frequency.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+#[reflect(Component)]
+pub enum RenderFrequency {
+    /// Renders every frame, like a camera without `RenderFrequency`.
+    #[default]
+    EveryFrame,
+    /// Renders on the frame it is set, then only when a [`RequestDimensionRender`] is sent.
+    Once,
+    /// Renders only when a [`RequestDimensionRender`] is sent, send one whenever the content changes.
+    OnRequest,
+}
+
+/// Renders the dimension camera `0` on this frame, see [`RenderFrequency`].
+#[derive(Debug, Clone, Copy)]
+pub struct RequestDimensionRender(pub Entity);
+
+/// Activates the cameras with a [`RenderFrequency`] on the frames they should render, and deactivates them otherwise.
+///
+/// The cameras frozen by [`FreezeDimensions`] are left to [`apply_freeze_dimensions`]: activating them here
+/// only for it to deactivate them would change them every frame.
+pub fn apply_render_frequency(
+    mut requests: EventReader<RequestDimensionRender>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut cameras: Query<(Entity, &mut Camera, Ref<RenderFrequency>), Without<Frozen>>,
+) {
+    let requested: Vec<_> = requests.iter().map(|request| request.0).collect();
+    let modified: Vec<_> = image_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle),
+            _ => None,
+        })
+        .collect();
+    for (entity, mut camera, frequency) in cameras.iter_mut() {
+        let resized = match &camera.target {
+            RenderTarget::Image(image) => modified.contains(&image),
+            RenderTarget::Window(_) => false,
+        };
+        let active = match *frequency {
+            RenderFrequency::EveryFrame => true,
+            RenderFrequency::Once if frequency.is_changed() => true,
+            RenderFrequency::Once | RenderFrequency::OnRequest => {
+                resized || requested.contains(&entity)
+            }
+        };
+        // Only touch the camera when needed, to not trigger its change detection every frame.
+        if camera.is_active != active {
+            camera.is_active = active;
+        }
+    }
+}
+
+/// Freezes every dimension camera when `true`, for a freeze-frame effect: they stop rendering,
+/// and their images keep the last rendered frame, which the post process keeps compositing.
+/// Switching dimensions and transitioning between the frozen frames still works.
+///
+/// Resizing a frozen image clears it, it then stays empty until the dimensions are unfrozen.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FreezeDimensions(pub bool);
+
+/// Marks the dimension cameras deactivated by [`FreezeDimensions`], to activate them back.
+#[derive(Component)]
+struct Frozen;
+
+/// Deactivates the cameras rendering to a dimension image while [`FreezeDimensions`] is `true`,
+/// and activates them back once it is `false`.
+///
+/// It runs after [`apply_render_frequency`], which would activate some of them otherwise.
+pub fn apply_freeze_dimensions(
+    mut commands: Commands,
+    freeze: Res<FreezeDimensions>,
+    dimensions: Query<(&Dimensions, Option<&ForegroundDimensions>)>,
+    mut cameras: Query<(Entity, &mut Camera, Option<&Frozen>)>,
+) {
+    if !freeze.0 {
+        // Only the cameras it deactivated are activated back, the others were inactive before the freeze.
+        // Like below, `is_active` is only written when it changes, to not trigger the change detection.
+        for (entity, mut camera, frozen) in cameras.iter_mut() {
+            if frozen.is_some() {
+                if !camera.is_active {
+                    camera.is_active = true;
+                }
+                commands.entity(entity).remove::<Frozen>();
+            }
+        }
+        return;
+    }
+    let images: Vec<_> = dimensions
+        .iter()
+        .flat_map(|(dimensions, foreground)| {
+            dimensions
+                .dimensions
+                .iter()
+                .chain(foreground.iter().flat_map(|foreground| foreground.dimensions.iter()))
+        })
+        .map(|dimension| &dimension.image)
+        .collect();
+    for (entity, mut camera, frozen) in cameras.iter_mut() {
+        let RenderTarget::Image(image) = &camera.target else {
+            continue;
+        };
+        if camera.is_active && images.contains(&image) {
+            camera.is_active = false;
+            if frozen.is_none() {
+                commands.entity(entity).insert(Frozen);
+            }
+        }
+    }
+}
+
+/// Makes a dimension camera follow `main_camera`, for dimension cameras which are not its children.
+///
+/// Every frame, the global transform of the main camera is copied to the dimension camera,
+/// its translation scaled by `parallax`, then `offset` is applied on top of it.
+/// The dimension camera must not have a parent, its `Transform` is overwritten.
+#[derive(Component, Clone, Copy, Debug, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct SyncDimensionCameraTransform {
+    /// The camera to follow, usually the one with the `Dimensions`.
+    pub main_camera: Entity,
+    /// Applied in the local space of the synced transform.
+    pub offset: Transform,
+    /// How much of the main camera translation is followed, `1.0` follows it exactly,
+    /// lower values move the dimension slower, like a distant parallax layer.
+    pub parallax: f32,
+}
+
+impl Default for SyncDimensionCameraTransform {
+    fn default() -> Self {
+        Self::new(Entity::PLACEHOLDER)
+    }
+}
+
+impl SyncDimensionCameraTransform {
+    /// Follows `main_camera` exactly.
+    pub fn new(main_camera: Entity) -> Self {
+        Self {
+            main_camera,
+            offset: Transform::IDENTITY,
+            parallax: 1.0,
+        }
+    }
+}
+
+/// Copies the transform of the main cameras to the dimension cameras with a [`SyncDimensionCameraTransform`].
+///
+/// It runs after the transforms are propagated, so it sets the `GlobalTransform` as well.
+pub fn sync_dimension_camera_transforms(
+    main_cameras: Query<&GlobalTransform, Without<SyncDimensionCameraTransform>>,
+    mut dimension_cameras: Query<(
+        &SyncDimensionCameraTransform,
+        &mut Transform,
+        &mut GlobalTransform,
+    )>,
+) {
+    for (sync, mut transform, mut global_transform) in dimension_cameras.iter_mut() {
+        let Ok(main_transform) = main_cameras.get(sync.main_camera) else {
+            continue;
+        };
+        let (scale, rotation, translation) = main_transform.to_scale_rotation_translation();
+        let synced = Transform {
+            translation: translation * sync.parallax,
+            rotation,
+            scale,
+        }
+        .mul_transform(sync.offset);
+        *transform = synced;
+        *global_transform = synced.into();
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc, Mutex,
+};
+
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, DiagnosticsStore},
+    prelude::*,
+    render::{
+        render_resource::{Buffer, BufferDescriptor, BufferUsages, Maintain, MapMode},
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        settings::WgpuFeatures,
+        Render, RenderApp, RenderSet,
+    },
+};
+
+/// Measures the GPU time of the post process pass, into the [`DiagnosticsStore`]
+/// under [`PostProcessDiagnosticsPlugin::PASS_TIME`], in milliseconds.
+///
+/// The GPU must support [`WgpuFeatures::TIMESTAMP_QUERY`], which the `WgpuSettings` of the `RenderPlugin`
+/// must request. Without it, no measurement is recorded.
+/// The measurement is read back from the GPU a few frames later, and covers the pass of a single view per frame.
+pub struct PostProcessDiagnosticsPlugin;
+
+impl PostProcessDiagnosticsPlugin {
+    /// The GPU time of the post process pass, in milliseconds.
+    pub const PASS_TIME: DiagnosticId =
+        DiagnosticId::from_u128(270_386_128_455_711_254_203_981_602_356_094_117_021);
+}
+
+impl Plugin for PostProcessDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let timings = PassTimings::default();
+        app.init_resource::<DiagnosticsStore>()
+            .insert_resource(timings.clone())
+            .add_systems(Update, record_pass_time);
+        app.world
+            .resource_mut::<DiagnosticsStore>()
+            .add(Diagnostic::new(Self::PASS_TIME, "post_process_pass_ms", 20));
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(timings)
+            // After the frame is submitted, so the timestamps are written.
+            .add_systems(Render, read_pass_timestamps.after(RenderSet::Render));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        let render_device = render_app.world.resource::<RenderDevice>();
+        if !render_device
+            .features()
+            .contains(WgpuFeatures::TIMESTAMP_QUERY)
+        {
+            warn!("PostProcessDiagnosticsPlugin: the GPU doesn't support timestamp queries, the post process pass will not be measured");
+            return;
+        }
+        let timestamps = PassTimestamps::new(render_device);
+        render_app.insert_resource(timestamps);
+    }
+}
+
+/// The last measured pass time, in milliseconds, shared between the render world which reads it back
+/// and the main world which records it.
+#[derive(Resource, Clone, Default)]
+struct PassTimings(Arc<Mutex<Option<f64>>>);
+
+/// No timestamps are being measured.
+const IDLE: u8 = 0;
+/// The timestamps of this frame are written, they are read back after the frame is submitted.
+const WRITTEN: u8 = 1;
+/// The readback buffer is being mapped.
+const MAPPING: u8 = 2;
+
+/// The GPU resources measuring the pass, only present when timestamp queries are supported.
+#[derive(Resource)]
+pub(crate) struct PassTimestamps {
+    query_set: wgpu::QuerySet,
+    /// The resolved timestamps, which can't be mapped.
+    resolve_buffer: Buffer,
+    readback_buffer: Buffer,
+    /// One of [`IDLE`], [`WRITTEN`] or [`MAPPING`]: a new measurement starts once the previous one is read back.
+    state: Arc<AtomicU8>,
+}
+
+/// Two 8 bytes timestamps, before and after the pass.
+const TIMESTAMPS_SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+impl PassTimestamps {
+    fn new(render_device: &RenderDevice) -> Self {
+        let query_set = render_device
+            .wgpu_device()
+            .create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("post_process_timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            });
+        let resolve_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("post_process_timestamps_resolve_buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("post_process_timestamps_readback_buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            state: Arc::new(AtomicU8::new(IDLE)),
+        }
+    }
+
+    /// Writes the timestamp before the pass, returns whether this pass is measured.
+    pub(crate) fn begin(&self, render_context: &mut RenderContext) -> bool {
+        if self.state.load(Ordering::Acquire) != IDLE {
+            return false;
+        }
+        render_context
+            .command_encoder()
+            .write_timestamp(&self.query_set, 0);
+        true
+    }
+
+    /// Writes the timestamp after the pass and copies both to the readback buffer.
+    pub(crate) fn end(&self, render_context: &mut RenderContext) {
+        let encoder = render_context.command_encoder();
+        encoder.write_timestamp(&self.query_set, 1);
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            TIMESTAMPS_SIZE,
+        );
+        self.state.store(WRITTEN, Ordering::Release);
+    }
+}
+
+fn read_pass_timestamps(
+    timestamps: Option<Res<PassTimestamps>>,
+    timings: Res<PassTimings>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    let Some(timestamps) = timestamps else {
+        return;
+    };
+    if timestamps
+        .state
+        .compare_exchange(WRITTEN, MAPPING, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return;
+    }
+    // Nanoseconds per timestamp tick.
+    let period = render_queue.get_timestamp_period() as f64;
+    let timings = timings.clone();
+    let state = timestamps.state.clone();
+    let mapped_buffer = timestamps.readback_buffer.clone();
+    timestamps
+        .readback_buffer
+        .slice(..)
+        .map_async(MapMode::Read, move |result| {
+            if let Err(err) = result {
+                warn!("PostProcessDiagnosticsPlugin: failed to read back the pass timestamps: {err}");
+                state.store(IDLE, Ordering::Release);
+                return;
+            }
+            let (start, end) = {
+                let mapped = mapped_buffer.slice(..).get_mapped_range();
+                let (start, end) = mapped.split_at(8);
+                (
+                    u64::from_le_bytes(start.try_into().unwrap()),
+                    u64::from_le_bytes(end.try_into().unwrap()),
+                )
+            };
+            mapped_buffer.unmap();
+            let milliseconds = end.saturating_sub(start) as f64 * period / 1_000_000.0;
+            *timings.0.lock().unwrap() = Some(milliseconds);
+            state.store(IDLE, Ordering::Release);
+        });
+    // The mapping callback runs when the device is polled, once the frame is done.
+    render_device.wgpu_device().poll(Maintain::Poll);
+}
+
+fn record_pass_time(timings: Res<PassTimings>, mut diagnostics: Diagnostics) {
+    if let Some(milliseconds) = timings.0.lock().unwrap().take() {
+        diagnostics.add_measurement(PostProcessDiagnosticsPlugin::PASS_TIME, || milliseconds);
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt;
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{render_resource::Extent3d, view::RenderLayers},
+};
+
+use crate::{
+    camera::resize_render_target, create_camera, create_depth_camera, DimensionDef, ResizePolicy,
+    DIMENSION_CAMERA_ORDER,
+};
+
+/// Keeps track of the render layers used by dimensions.
+///
+/// Layer 0 is the default layer, used by the main pass camera, so it is never handed out.
+#[derive(Resource, Debug, Clone)]
+pub struct DimensionLayers {
+    /// One bit per render layer, set when the layer is in use.
+    used: u32,
+}
+
+impl Default for DimensionLayers {
+    fn default() -> Self {
+        Self { used: 1 }
+    }
+}
+
+impl DimensionLayers {
+    /// Reserves a render layer which is not used by any other dimension.
+    pub fn allocate(&mut self) -> Result<RenderLayers, OutOfRenderLayers> {
+        let layer = (0..RenderLayers::TOTAL_LAYERS as u8)
+            .find(|layer| self.used & (1 << layer) == 0)
+            .ok_or(OutOfRenderLayers)?;
+        self.used |= 1 << layer;
+        Ok(RenderLayers::layer(layer))
+    }
+
+    /// Makes the given render layers available again, call this when their dimension is removed.
+    ///
+    /// The default layer 0 is never freed.
+    pub fn free(&mut self, render_layers: RenderLayers) {
+        for layer in render_layers.iter().filter(|layer| *layer != 0) {
+            self.used &= !(1 << layer);
+        }
+    }
+}
+
+/// Returned when all of the [`RenderLayers::TOTAL_LAYERS`] render layers are already in use,
+/// or when a render layer past the last one is requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRenderLayers;
+
+impl fmt::Display for OutOfRenderLayers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "only {} render layers are available: they are all used by dimensions, or a later one was requested",
+            RenderLayers::TOTAL_LAYERS
+        )
+    }
+}
+
+impl std::error::Error for OutOfRenderLayers {}
+
+/// A dimension created by [`DimensionBuilder`].
+pub struct BuiltDimension {
+    /// To be added to the [`Dimensions`](crate::Dimensions) of the main pass camera.
+    pub def: DimensionDef,
+    /// The render layer to spawn the dimension content onto.
+    pub render_layers: RenderLayers,
+    /// The camera rendering the dimension, it is not parented to anything.
+    pub camera: Entity,
+}
+
+/// Creates a dimension camera and its render target, on a render layer of its own.
+pub struct DimensionBuilder {
+    size: Extent3d,
+    resize_policy: ResizePolicy,
+    clear_color: ClearColorConfig,
+    depth: bool,
+    order: isize,
+    projection: Option<OrthographicProjection>,
+    mip_level_count: u32,
+}
+
+impl DimensionBuilder {
+    /// `size` is the initial size of the dimension texture, each dimension can have its own.
+    pub fn new(size: Extent3d) -> Self {
+        Self {
+            size,
+            resize_policy: ResizePolicy::default(),
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            depth: false,
+            order: DIMENSION_CAMERA_ORDER,
+            projection: None,
+            mip_level_count: 1,
+        }
+    }
+
+    /// Sets how the dimension texture reacts to the window being resized.
+    pub fn resize_policy(mut self, resize_policy: ResizePolicy) -> Self {
+        self.resize_policy = resize_policy;
+        self
+    }
+
+    /// Renders the dimension at a fixed internal resolution, whatever the window size.
+    ///
+    /// The post process scales it to the window, see `ScalingMode` for crisp pixel art.
+    pub fn internal_resolution(mut self, resolution: UVec2) -> Self {
+        self.size = Extent3d {
+            width: resolution.x,
+            height: resolution.y,
+            ..default()
+        };
+        self.resize_policy = ResizePolicy::InternalResolution(resolution);
+        self
+    }
+
+    /// Sets how the dimension camera clears its texture, black by default.
+    ///
+    /// See [`create_camera`] for transparent dimensions.
+    pub fn clear_color(mut self, clear_color: ClearColorConfig) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// Renders the dimension with a 3d camera whose depth is used for depth compositing.
+    ///
+    /// See [`create_depth_camera`].
+    pub fn depth(mut self) -> Self {
+        self.depth = true;
+        self
+    }
+
+    /// Sets the order of the dimension camera, [`DIMENSION_CAMERA_ORDER`] by default.
+    ///
+    /// It must be lower than the order of the camera compositing the dimension.
+    /// To render a dimension which shows another one, give it a higher order than the other dimension camera.
+    pub fn order(mut self, order: isize) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets the projection of the dimension camera, to zoom in or out, see [`create_camera`].
+    ///
+    /// The default is the usual orthographic projection for 2d dimensions, and a perspective one for
+    /// [`depth`](Self::depth) dimensions.
+    pub fn projection(mut self, projection: OrthographicProjection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Gives the dimension texture mip levels, for [`DimensionDef::lod_bias`].
+    ///
+    /// See [`create_mipmapped_render_target`](crate::create_mipmapped_render_target) for their cost.
+    pub fn mip_level_count(mut self, mip_level_count: u32) -> Self {
+        self.mip_level_count = mip_level_count;
+        self
+    }
+
+    pub fn build(
+        self,
+        layers: &mut DimensionLayers,
+        images: &mut Assets<Image>,
+        commands: &mut Commands,
+    ) -> Result<BuiltDimension, OutOfRenderLayers> {
+        let render_layers = layers.allocate()?;
+        let (image, camera) = if self.depth {
+            let (image, camera) = create_depth_camera(
+                self.size,
+                images,
+                commands,
+                render_layers,
+                self.clear_color,
+                self.order,
+            );
+            if let Some(projection) = self.projection {
+                commands
+                    .entity(camera)
+                    .insert(Projection::Orthographic(projection));
+            }
+            (image, camera)
+        } else {
+            create_camera(
+                self.size,
+                images,
+                commands,
+                render_layers,
+                self.clear_color,
+                self.order,
+                self.projection.unwrap_or_default(),
+            )
+        };
+        if self.mip_level_count > 1 {
+            if let Some(image) = images.get_mut(&image) {
+                image.texture_descriptor.mip_level_count = self.mip_level_count;
+                resize_render_target(image, self.size);
+            }
+        }
+        Ok(BuiltDimension {
+            def: DimensionDef {
+                image,
+                resize_policy: self.resize_policy,
+                depth_camera: self.depth.then_some(camera),
+                render_layers: Some(render_layers),
+                ..default()
+            },
+            render_layers,
+            camera,
+        })
+    }
+}
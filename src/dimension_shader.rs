@@ -0,0 +1,253 @@
+use bevy::{
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    render::{
+        globals::{GlobalsBuffer, GlobalsUniform},
+        render_asset::RenderAssets,
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d, FilterMode,
+            FragmentState, LoadOp, MultisampleState, Operations, PipelineCache, PrimitiveState,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipelineDescriptor, Sampler,
+            SamplerBindingType, SamplerDescriptor, ShaderStages, ShaderType, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::GpuImage,
+        Render, RenderApp, RenderSet,
+    },
+    utils::{HashMap, HashSet},
+};
+
+use crate::post_process::ExtractedDimensions;
+
+/// Runs the [`DimensionDef::shader`](crate::DimensionDef::shader) of the bound dimensions,
+/// each into a texture the post process then samples in place of the dimension image.
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub(crate) struct DimensionShaderPlugin;
+
+impl Plugin for DimensionShaderPlugin {
+    fn build(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.add_systems(Render, prepare_dimension_shaders.in_set(RenderSet::Prepare));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<DimensionShaders>();
+    }
+}
+
+/// The pipelines of the dimension shaders, and the textures they render to.
+#[derive(Resource)]
+struct DimensionShaders {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    /// One pipeline per shader and dimension image format.
+    pipelines: HashMap<(Handle<Shader>, TextureFormat), CachedRenderPipelineId>,
+    /// The shaded textures, per dimension image and shader.
+    textures: HashMap<(Handle<Image>, Handle<Shader>), ShadedDimension>,
+}
+
+struct ShadedDimension {
+    view: TextureView,
+    size: Vec2,
+    format: TextureFormat,
+    pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for DimensionShaders {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_dimension_shader_bind_group_layout"),
+            entries: &[
+                // The dimension image
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // The globals, for animated shaders
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: Some(GlobalsUniform::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+        Self {
+            layout,
+            sampler,
+            pipelines: HashMap::default(),
+            textures: HashMap::default(),
+        }
+    }
+}
+
+/// Queues the pipelines of the dimension shaders in use, and creates the textures they render to.
+///
+/// The textures follow the size of their dimension image, those which are not used anymore are dropped.
+fn prepare_dimension_shaders(
+    mut shaders: ResMut<DimensionShaders>,
+    views: Query<&ExtractedDimensions>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+) {
+    let shaders = &mut *shaders;
+    let mut used = HashSet::new();
+    for dimension in views.iter().flat_map(|dimensions| dimensions.slots.iter().flatten()) {
+        let Some(shader) = &dimension.shader else {
+            continue;
+        };
+        let Some(image) = gpu_images.get(&dimension.image) else {
+            continue;
+        };
+        let key = (dimension.image.clone_weak(), shader.clone_weak());
+        if !used.insert(key.clone()) {
+            continue;
+        }
+        if let Some(shaded) = shaders.textures.get(&key) {
+            if shaded.size == image.size && shaded.format == image.texture_format {
+                continue;
+            }
+        }
+
+        let pipeline_id = *shaders
+            .pipelines
+            .entry((shader.clone_weak(), image.texture_format))
+            .or_insert_with(|| {
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("post_process_dimension_shader_pipeline".into()),
+                    layout: vec![shaders.layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: shader.clone(),
+                        shader_defs: vec![],
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: image.texture_format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+            });
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("post_process_shaded_dimension"),
+            size: Extent3d {
+                width: image.size.x as u32,
+                height: image.size.y as u32,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: image.texture_format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        shaders.textures.insert(
+            key,
+            ShadedDimension {
+                view: texture.create_view(&TextureViewDescriptor::default()),
+                size: image.size,
+                format: image.texture_format,
+                pipeline_id,
+            },
+        );
+    }
+    shaders.textures.retain(|key, _| used.contains(key));
+}
+
+/// Runs the `shader` of a dimension on its `image`, returns the texture to sample in its place.
+///
+/// `None` until the texture and the pipeline are ready, the dimension image is then sampled as is.
+pub(crate) fn shade_dimension<'w>(
+    world: &'w World,
+    render_context: &mut RenderContext,
+    image_handle: &Handle<Image>,
+    image: &GpuImage,
+    shader: &Handle<Shader>,
+) -> Option<&'w TextureView> {
+    let shaders = world.get_resource::<DimensionShaders>()?;
+    let shaded = shaders
+        .textures
+        .get(&(image_handle.clone_weak(), shader.clone_weak()))?;
+    let pipeline = world
+        .resource::<PipelineCache>()
+        .get_render_pipeline(shaded.pipeline_id)?;
+    let globals_binding = world.resource::<GlobalsBuffer>().buffer.binding()?;
+
+    let bind_group = render_context
+        .render_device()
+        .create_bind_group(&BindGroupDescriptor {
+            label: Some("post_process_dimension_shader_bind_group"),
+            layout: &shaders.layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&image.texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&shaders.sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: globals_binding,
+                },
+            ],
+        });
+
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("post_process_dimension_shader_pass"),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: &shaded.view,
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(Color::NONE.into()),
+                store: true,
+            },
+        })],
+        depth_stencil_attachment: None,
+    });
+    render_pass.set_render_pipeline(pipeline);
+    render_pass.set_bind_group(0, &bind_group, &[]);
+    render_pass.draw(0..3, 0..1);
+
+    Some(&shaded.view)
+}
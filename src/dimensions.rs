@@ -0,0 +1,644 @@
+use bevy::{
+    ecs::event::ManualEventReader,
+    prelude::*,
+    render::{render_resource::Extent3d, view::RenderLayers},
+    utils::Duration,
+    window::WindowResized,
+};
+
+use crate::{camera::resize_render_target, DimensionTransition};
+
+/// The dimensions composited by the post process of the camera they are on.
+///
+/// Only the `selected` dimension is shown, the others are blended in by the shader.
+///
+/// A `Dimensions` without entries is valid: as long as the `ForegroundDimensions` are empty too,
+/// the post process is skipped and the main pass is shown untouched.
+#[derive(Component, Default, Debug, Clone, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct Dimensions {
+    pub dimensions: Vec<DimensionDef>,
+    /// Index of the shown dimension in `dimensions`.
+    pub selected: u32,
+}
+
+impl Dimensions {
+    /// One dimension per image, with the default settings, the first one selected.
+    pub fn from_handles(images: impl IntoIterator<Item = Handle<Image>>) -> Self {
+        Self {
+            dimensions: images
+                .into_iter()
+                .map(|image| DimensionDef {
+                    image,
+                    ..default()
+                })
+                .collect(),
+            selected: 0,
+        }
+    }
+
+    /// Adds a dimension after the existing ones.
+    pub fn add_dimension(mut self, dimension: DimensionDef) -> Self {
+        self.dimensions.push(dimension);
+        self
+    }
+
+    /// Selects the dimension at `index` in `dimensions`.
+    pub fn select(mut self, index: u32) -> Self {
+        self.selected = index;
+        self
+    }
+
+    /// Index of the first dimension named `name`.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.dimensions
+            .iter()
+            .position(|dimension| dimension.name.as_deref() == Some(name))
+    }
+
+    /// Selects the first dimension named `name`, so the selection doesn't depend on the order of `dimensions`.
+    ///
+    /// Returns `false` and keeps the selection if there is no such dimension.
+    pub fn select_by_name(&mut self, name: &str) -> bool {
+        let Some(index) = self.index_of(name) else {
+            return false;
+        };
+        self.selected = index as u32;
+        true
+    }
+
+    /// Index in `dimensions` of the dimension being displayed, or being transitioned to.
+    ///
+    /// During a `DimensionTransition`, it is already the new dimension, the previous one is
+    /// `DimensionTransition::from` and the progress is `DimensionTransition::progress`.
+    /// When a transition is interrupted by another switch, it is the latest selected dimension,
+    /// and a new transition is chained from the dimension which was being transitioned to.
+    /// A `selected` out of the bounds of `dimensions` wraps around, like it does in the post process.
+    pub fn displayed_index(&self) -> u32 {
+        self.view_selected(0)
+    }
+
+    /// The progress of the latest transition of these dimensions, `None` when none is running.
+    ///
+    /// The transition state lives in the [`DimensionTransition`] on the same entity, pass it along:
+    /// this forwards to [`DimensionTransition::progress`].
+    pub fn transition_progress(&self, transition: Option<&DimensionTransition>) -> Option<f32> {
+        transition.and_then(DimensionTransition::progress)
+    }
+
+    /// The dimension shown by a view whose selection is shifted by `offset`, see [`DimensionViewOffset`].
+    pub fn view_selected(&self, offset: u32) -> u32 {
+        match self.dimensions.len() as u64 {
+            0 => self.selected,
+            count => ((self.selected as u64 + offset as u64) % count) as u32,
+        }
+    }
+}
+
+/// Temporarily shows another dimension than the selected one, while `active`, on the camera it is on.
+///
+/// The `selected` of the `Dimensions` is untouched, so releasing the peek goes back to it.
+/// With `crossfade`, the `DimensionTransition` of the camera transitions to the peeked dimension and back,
+/// otherwise the peek is instant and interrupts the running transition.
+/// While peeking, the `DimensionViewOffset` of the camera is ignored.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct PeekDimension {
+    /// Index in `Dimensions::dimensions` of the peeked dimension.
+    pub index: u32,
+    pub active: bool,
+    pub crossfade: bool,
+}
+
+impl PeekDimension {
+    /// The dimension shown by the camera with these `dimensions`: the peeked one while active,
+    /// otherwise the selected one.
+    pub fn displayed(peek: Option<&Self>, dimensions: &Dimensions) -> u32 {
+        match peek.filter(|peek| peek.active) {
+            Some(peek) => peek.index % (dimensions.dimensions.len() as u32).max(1),
+            None => dimensions.selected,
+        }
+    }
+}
+
+/// Shifts the dimension shown by the camera it is on, relative to the `selected` of its `Dimensions`.
+///
+/// For a split-screen, cameras with the same `Dimensions` and offsets of 0 and 1 show two neighbouring dimensions,
+/// and keep doing so as the selection changes. The post process is confined to the viewport of each camera,
+/// so each one composites its own dimension into its own part of the screen.
+/// Only the background `Dimensions` are shifted, the `ForegroundDimensions` are not.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct DimensionViewOffset(pub u32);
+
+/// A second, independent group of dimensions, composited over the `Dimensions` of the same camera.
+///
+/// Its selected dimension is drawn over the background using its alpha,
+/// so its cameras should clear to a transparent color. It requires `Dimensions` on the same camera.
+#[derive(Component, Default, Debug, Clone, Deref, DerefMut, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct ForegroundDimensions(pub Dimensions);
+
+#[derive(Debug, Clone, Reflect, FromReflect)]
+pub struct DimensionDef {
+    /// Identifies the dimension for [`Dimensions::select_by_name`].
+    pub name: Option<String>,
+    /// The texture a dimension camera renders to, see [`create_camera`](crate::create_camera).
+    ///
+    /// Its sampler descriptor is ignored, see `PostProcessSettings::scaling_mode` and `PostProcessPlugin::address_mode_u`.
+    pub image: Handle<Image>,
+    /// What updates `image`, a dimension camera by default.
+    pub source: DimensionSource,
+    /// Added to the sampled UV, after `uv_scale` is applied.
+    pub uv_offset: Vec2,
+    /// Multiplies the sampled UV. Values outside of 0..1 are resolved by the sampler address mode,
+    /// see `PostProcessPlugin::address_mode_u`: with `AddressMode::Repeat`, `Vec2::splat(2.0)` tiles the texture twice.
+    pub uv_scale: Vec2,
+    /// An affine transform of the sampled UV, applied around the center of the dimension before
+    /// `uv_scale` and `uv_offset`, for rotated or skewed dimensions: `Mat3::from_angle(angle)` spins it.
+    /// Only its first two rows are used. Like `uv_scale`, UVs ending up outside of 0..1 are resolved
+    /// by the sampler address mode.
+    pub uv_matrix: Mat3,
+    /// Flips the dimension horizontally, like a mirror, before the other UV transforms.
+    pub flip_x: bool,
+    /// Flips the dimension vertically, before the other UV transforms.
+    pub flip_y: bool,
+    /// How the dimension texture reacts to the window being resized.
+    pub resize_policy: ResizePolicy,
+    /// Multiplies the sampled alpha, to fade the dimension independently of the others.
+    pub alpha: f32,
+    /// How much the dimension is rendered in grayscale, from 0 (full color) to 1 (grayscale).
+    pub desaturate: f32,
+    /// Blurs the dimension over this many texels of its texture, for a dreamy look. `0.0` disables it.
+    ///
+    /// The blur always takes 9 samples, larger radii look blocky: the radius is clamped to 4 texels.
+    pub blur_radius: f32,
+    /// The mip level the dimension is sampled at, for a distant, blurry look which is cheaper than `blur_radius`.
+    /// `0.0` samples the full resolution, each level up halves it.
+    ///
+    /// Only has an effect if the image has mips, see [`create_mipmapped_render_target`](crate::create_mipmapped_render_target).
+    /// Fractional levels blend the two nearest levels with `ScalingMode::Linear`, and snap to the nearest one
+    /// with `ScalingMode::Nearest` or non-filterable dimension textures.
+    pub lod_bias: f32,
+    /// Whether the dimension texture holds premultiplied alpha colors, like transparent overlays rendered
+    /// with premultiplied blending. They are converted to straight alpha when sampled, otherwise their
+    /// edges would be darkened twice by the straight alpha composite.
+    ///
+    /// The sRGB dimension textures are decoded to linear colors when sampled, and the un-premultiplication
+    /// happens in linear space: this is right for colors premultiplied in linear space, which is what
+    /// the GPU blending into an sRGB texture does.
+    pub premultiplied: bool,
+    /// The 3d camera rendering this dimension, its depth is used for depth compositing.
+    /// See [`create_depth_camera`](crate::create_depth_camera).
+    pub depth_camera: Option<Entity>,
+    /// The render layers the dimension content is spawned onto, for debugging tools.
+    ///
+    /// Set by [`DimensionBuilder`](crate::DimensionBuilder), it has no effect on rendering.
+    pub render_layers: Option<RenderLayers>,
+    /// A shader processing this dimension on its own, before it is composited: a pixelation or a blur
+    /// of this dimension only. `None` samples the dimension image as is.
+    ///
+    /// It renders a fullscreen triangle into a texture of the size and format of the dimension image,
+    /// which the post process then samples instead. It must import `bevy_core_pipeline::fullscreen_vertex_shader`,
+    /// define `@fragment fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32>`, and can bind:
+    /// - `@group(0) @binding(0) var dimension_texture: texture_2d<f32>`, the dimension image,
+    /// - `@group(0) @binding(1) var dimension_sampler: sampler`, a linear sampler,
+    /// - `@group(0) @binding(2) var<uniform> globals: Globals`, from `bevy_render::globals`.
+    ///
+    /// Not supported with `DimensionStorage::TextureArray`, which copies the dimension images.
+    pub shader: Option<Handle<Shader>>,
+    /// Skips sampling this dimension when its camera had nothing visible to render, it then shows as transparent.
+    ///
+    /// This is an approximation based on the frustum culling of the camera: an entity whose bounding box
+    /// is in view counts as visible content, even if nothing of it is actually drawn. Worth it for many
+    /// sparse dimensions. The camera is found by its render target, the dimension `image`.
+    pub cull: bool,
+}
+
+impl Default for DimensionDef {
+    fn default() -> Self {
+        Self {
+            name: None,
+            image: Handle::default(),
+            source: DimensionSource::default(),
+            uv_offset: Vec2::ZERO,
+            uv_scale: Vec2::ONE,
+            uv_matrix: Mat3::IDENTITY,
+            flip_x: false,
+            flip_y: false,
+            resize_policy: ResizePolicy::default(),
+            alpha: 1.0,
+            desaturate: 0.0,
+            blur_radius: 0.0,
+            lod_bias: 0.0,
+            premultiplied: false,
+            depth_camera: None,
+            render_layers: None,
+            shader: None,
+            cull: false,
+        }
+    }
+}
+
+impl DimensionDef {
+    /// The UV sampled in the dimension image for the UV `uv` of the composite, like the post process does:
+    /// the flips, `uv_matrix` around the center, then `uv_scale` and `uv_offset`.
+    ///
+    /// UVs outside of 0..1 are returned as is, the sampler address mode resolves them.
+    pub fn image_uv(&self, uv: Vec2) -> Vec2 {
+        let flipped = Vec2::new(
+            if self.flip_x { 1.0 - uv.x } else { uv.x },
+            if self.flip_y { 1.0 - uv.y } else { uv.y },
+        );
+        let transformed = (self.uv_matrix * (flipped - 0.5).extend(1.0)).truncate() + 0.5;
+        transformed * self.uv_scale + self.uv_offset
+    }
+
+    /// A dimension showing an image updated by something else than a camera, see [`DimensionSource::External`].
+    pub fn external(image: Handle<Image>) -> Self {
+        Self {
+            image,
+            source: DimensionSource::External,
+            ..default()
+        }
+    }
+}
+
+/// What updates the image of a dimension.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum DimensionSource {
+    /// A camera renders to the image, see [`create_camera`](crate::create_camera).
+    #[default]
+    Camera,
+    /// The image is updated by other means, e.g. a video decoder writing its frames into `Image::data`.
+    ///
+    /// The image only needs the `TEXTURE_BINDING` usage, and `COPY_DST` to upload its changes,
+    /// which `Image` assets have by default. It is not a render target, so:
+    /// - it keeps its size when the window is resized, whatever the `resize_policy`,
+    /// - its mips are not generated, upload them along with the image for `lod_bias`,
+    /// - `cull` must stay off, there is no camera to tell whether it has content.
+    External,
+}
+
+/// How a dimension texture is resized when the window is resized.
+///
+/// Dimensions don't need to share the same size: they are sampled with normalized UVs.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect)]
+pub enum ResizePolicy {
+    /// The texture follows the window size, multiplied by the given factor.
+    Proportional(f32),
+    /// The texture keeps the size it was created with.
+    Fixed,
+    /// The texture is kept at this internal resolution whatever the window size,
+    /// only the final blit of the post process scales it to the window.
+    InternalResolution(UVec2),
+}
+
+impl Default for ResizePolicy {
+    fn default() -> Self {
+        Self::Proportional(1.0)
+    }
+}
+
+impl ResizePolicy {
+    /// The size a dimension texture should have for the given window size,
+    /// `None` if it should be left alone.
+    ///
+    /// Empty sizes, such as the one of a minimized window, are `None` as well:
+    /// textures can't be empty, the texture keeps its previous size instead.
+    pub fn size_for_window(&self, window_size: Vec2) -> Option<Extent3d> {
+        let size = match *self {
+            ResizePolicy::Proportional(factor) => (window_size * factor).as_uvec2(),
+            ResizePolicy::Fixed => return None,
+            ResizePolicy::InternalResolution(resolution) => resolution,
+        };
+        if size.x == 0 || size.y == 0 {
+            return None;
+        }
+        Some(Extent3d {
+            width: size.x,
+            height: size.y,
+            ..default()
+        })
+    }
+}
+
+/// Selects the next dimension of the `Dimensions` on the same entity every `interval` seconds.
+///
+/// Remove it or set `paused` to stop cycling.
+#[derive(Component, Debug, Clone, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct AutoCycle {
+    /// Seconds between two switches.
+    pub interval: f32,
+    pub timer: Timer,
+    pub paused: bool,
+}
+
+/// Switches every second.
+impl Default for AutoCycle {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl AutoCycle {
+    pub fn new(interval: f32) -> Self {
+        Self {
+            interval,
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+            paused: false,
+        }
+    }
+}
+
+/// Sends a [`SwitchDimension`] for every [`AutoCycle`] which is due.
+///
+/// A dimension switched by something else during this frame restarts its timer instead,
+/// so it's not switched twice.
+pub fn auto_cycle_system(
+    time: Res<Time>,
+    mut auto_cycles: Query<(Entity, &mut AutoCycle), With<Dimensions>>,
+    // Reading and sending the same event needs manual access to the `Events`.
+    mut switch_events: ResMut<Events<SwitchDimension>>,
+    mut switch_reader: Local<ManualEventReader<SwitchDimension>>,
+) {
+    let switched: Vec<Entity> = switch_reader
+        .iter(&switch_events)
+        .map(|switch| switch.entity)
+        .collect();
+    for (entity, mut auto_cycle) in auto_cycles.iter_mut() {
+        if auto_cycle.paused {
+            continue;
+        }
+        let interval = Duration::from_secs_f32(auto_cycle.interval);
+        if auto_cycle.timer.duration() != interval {
+            auto_cycle.timer.set_duration(interval);
+        }
+        if switched.contains(&entity) {
+            auto_cycle.timer.reset();
+            continue;
+        }
+        if auto_cycle.timer.tick(time.delta()).just_finished() {
+            switch_events.send(SwitchDimension {
+                entity,
+                direction: 1,
+            });
+        }
+    }
+    // Skip our own events, they must not restart the timers next frame.
+    switch_reader.iter(&switch_events).count();
+}
+
+/// Asks to change the selected dimension of the `Dimensions` on `entity`.
+///
+/// `direction` is added to the selected index, wrapping around:
+/// `1` selects the next dimension, `-1` the previous one.
+#[derive(Debug, Clone, Copy)]
+pub struct SwitchDimension {
+    pub entity: Entity,
+    pub direction: i32,
+}
+
+/// Applies the [`SwitchDimension`] events.
+///
+/// Systems sending them should run before this one to be applied on the same frame.
+pub fn apply_switch_dimension(
+    mut dim: Query<&mut Dimensions>,
+    mut switch_reader: EventReader<SwitchDimension>,
+) {
+    for switch in switch_reader.iter() {
+        let Ok(mut dimensions) = dim.get_mut(switch.entity) else {
+            continue;
+        };
+        let count = dimensions.dimensions.len() as u32;
+        dimensions.selected = step_selected(dimensions.selected, count, switch.direction);
+    }
+}
+
+/// The dimension after `current` among `count` dimensions, wrapping around to the first one.
+///
+/// Returns `current` when there are no dimensions.
+pub fn next_selected(current: u32, count: u32) -> u32 {
+    step_selected(current, count, 1)
+}
+
+/// The dimension before `current` among `count` dimensions, wrapping around to the last one.
+///
+/// Returns `current` when there are no dimensions.
+pub fn prev_selected(current: u32, count: u32) -> u32 {
+    step_selected(current, count, -1)
+}
+
+/// Moves `current` by `direction` among `count` dimensions, wrapping around.
+fn step_selected(current: u32, count: u32, direction: i32) -> u32 {
+    if count == 0 {
+        return current;
+    }
+    (current as i64 + direction as i64).rem_euclid(count as i64) as u32
+}
+
+/// How long the window size must stay the same before [`on_resize_system`] resizes the dimension textures.
+///
+/// A drag-resize sends a `WindowResized` every frame, reallocating every dimension texture each time
+/// would cause hitches: the resizes are coalesced into the last one. `Duration::ZERO` resizes immediately.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeDebounce(pub Duration);
+
+impl Default for ResizeDebounce {
+    fn default() -> Self {
+        Self(Duration::from_millis(150))
+    }
+}
+
+/// Resizes the dimension textures when the window is resized, following their [`ResizePolicy`].
+///
+/// The resize is delayed by the [`ResizeDebounce`], if any.
+pub fn on_resize_system(
+    mut images: ResMut<Assets<Image>>,
+    dim: Query<(&Dimensions, Option<&ForegroundDimensions>)>,
+    mut resize_reader: EventReader<WindowResized>,
+    debounce: Option<Res<ResizeDebounce>>,
+    time: Res<Time>,
+    // The last window size, and when it was received.
+    mut pending: Local<Option<(Vec2, Duration)>>,
+) {
+    if let Some(size) = resize_reader.iter().last() {
+        *pending = Some((Vec2::new(size.width, size.height), time.elapsed()));
+    }
+    let Some((window_size, resized_at)) = *pending else {
+        return;
+    };
+    let delay = debounce.map_or(Duration::ZERO, |debounce| debounce.0);
+    if time.elapsed() - resized_at < delay {
+        return;
+    }
+    *pending = None;
+    // A minimized window has an empty size, the textures keep theirs until it is restored.
+    if window_size.x < 1.0 || window_size.y < 1.0 {
+        return;
+    }
+
+    let size = Extent3d {
+        width: window_size.x as u32,
+        height: window_size.y as u32,
+        ..default()
+    };
+    for (dimensions, foreground) in dim.iter() {
+        resize_dimensions(&mut images, dimensions, size);
+        if let Some(foreground) = foreground {
+            resize_dimensions(&mut images, foreground, size);
+        }
+    }
+}
+
+/// Resizes the dimension textures of `dims` for a window of `size`, following their [`ResizePolicy`],
+/// e.g. when a quality setting changes the policies. Pass a [`ForegroundDimensions`] to resize its textures.
+///
+/// The size is clamped to at least 1 texel per axis, the images which are not loaded are skipped,
+/// as well as the [`DimensionSource::External`] ones.
+pub fn resize_dimensions(images: &mut Assets<Image>, dims: &Dimensions, size: Extent3d) {
+    let window_size = Vec2::new(size.width.max(1) as f32, size.height.max(1) as f32);
+    for dimension in dims.dimensions.iter() {
+        if dimension.source == DimensionSource::External {
+            continue;
+        }
+        let Some(size) = dimension.resize_policy.size_for_window(window_size) else {
+            continue;
+        };
+        resize_image(images, &dimension.image, size);
+    }
+}
+
+fn resize_image(images: &mut Assets<Image>, image: &Handle<Image>, size: Extent3d) {
+    if let Some(image) = images.get_mut(image) {
+        if image.texture_descriptor.size != size {
+            resize_render_target(image, size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_windows_dont_resize() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin::default())
+            .add_asset::<Image>()
+            .add_event::<WindowResized>()
+            .insert_resource(ResizeDebounce(Duration::ZERO))
+            .add_systems(Update, on_resize_system);
+        let size = Extent3d {
+            width: 640,
+            height: 360,
+            ..default()
+        };
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let background = crate::create_render_target(size, &mut images);
+        let foreground = crate::create_render_target(size, &mut images);
+        app.world.spawn((
+            Dimensions::from_handles([background.clone()]),
+            ForegroundDimensions(Dimensions::from_handles([foreground.clone()])),
+        ));
+        let resize = |app: &mut App, width: f32, height: f32| {
+            let mut events = app.world.resource_mut::<Events<WindowResized>>();
+            events.send(WindowResized {
+                window: Entity::PLACEHOLDER,
+                width,
+                height,
+            });
+            app.update();
+        };
+        let image_size = |app: &App, image: &Handle<Image>| {
+            let images = app.world.resource::<Assets<Image>>();
+            images.get(image).unwrap().texture_descriptor.size
+        };
+
+        // E.g. a minimized window.
+        resize(&mut app, 0.0, 0.0);
+        assert_eq!(image_size(&app, &background), size);
+        assert_eq!(image_size(&app, &foreground), size);
+        resize(&mut app, 320.0, 0.0);
+        assert_eq!(image_size(&app, &background), size);
+
+        // Restored.
+        resize(&mut app, 320.0, 180.0);
+        let restored = Extent3d {
+            width: 320,
+            height: 180,
+            ..default()
+        };
+        assert_eq!(image_size(&app, &background), restored);
+        assert_eq!(image_size(&app, &foreground), restored);
+    }
+
+    #[test]
+    fn resize_dimensions_follows_the_policies() {
+        let mut app = App::new();
+        app.add_plugin(AssetPlugin::default()).add_asset::<Image>();
+        let size = Extent3d {
+            width: 640,
+            height: 360,
+            ..default()
+        };
+        let mut images = app.world.resource_mut::<Assets<Image>>();
+        let mut dimension = |resize_policy, source| DimensionDef {
+            image: crate::create_render_target(size, &mut images),
+            resize_policy,
+            source,
+            ..default()
+        };
+        let dimensions = Dimensions::default()
+            .add_dimension(dimension(ResizePolicy::Proportional(0.5), default()))
+            .add_dimension(dimension(ResizePolicy::Fixed, default()))
+            .add_dimension(dimension(default(), DimensionSource::External));
+        let foreground = ForegroundDimensions(
+            Dimensions::default().add_dimension(dimension(default(), default())),
+        );
+
+        let window = Extent3d {
+            width: 320,
+            height: 180,
+            ..default()
+        };
+        resize_dimensions(&mut images, &dimensions, window);
+        resize_dimensions(&mut images, &foreground, window);
+        let image_size = |dimension: &DimensionDef| {
+            images
+                .get(&dimension.image)
+                .unwrap()
+                .texture_descriptor
+                .size
+        };
+        let half = Extent3d {
+            width: 160,
+            height: 90,
+            ..default()
+        };
+        assert_eq!(image_size(&dimensions.dimensions[0]), half);
+        assert_eq!(image_size(&dimensions.dimensions[1]), size);
+        assert_eq!(image_size(&dimensions.dimensions[2]), size);
+        assert_eq!(image_size(&foreground.dimensions[0]), window);
+    }
+
+    #[test]
+    fn selection_wraps_around() {
+        // Without dimensions the selection is kept.
+        assert_eq!(next_selected(0, 0), 0);
+        assert_eq!(prev_selected(0, 0), 0);
+        assert_eq!(step_selected(3, 0, -1), 3);
+        // A single dimension is always selected.
+        assert_eq!(next_selected(0, 1), 0);
+        assert_eq!(prev_selected(0, 1), 0);
+        let count = 3;
+        assert_eq!(next_selected(count - 1, count), 0);
+        assert_eq!(prev_selected(0, count), count - 1);
+        assert_eq!(step_selected(1, count, 5), 0);
+        assert_eq!(step_selected(1, count, -5), 2);
+        // An out of bounds selection wraps back in.
+        assert_eq!(next_selected(7, count), 2);
+    }
+}
@@ -0,0 +1,338 @@
+use bevy::{
+    asset::load_internal_asset,
+    ecs::query::QueryItem,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        extract_component::{ExtractComponent, ExtractComponentPlugin},
+        render_asset::RenderAssets,
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphApp, RenderGraphContext},
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntry, BindingResource, BindingType, CachedComputePipelineId,
+            ComputePassDescriptor, ComputePipelineDescriptor, Extent3d, PipelineCache,
+            ShaderStages, StorageTextureAccess, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+            TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+
+use crate::Dimensions;
+
+const DOWNSCALE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 7_301_624_998_215_473_019);
+
+/// The size of the compute workgroups, must match `downscale.wgsl`.
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Downscales a dimension texture with a compute pass before the post process,
+/// which then samples the smaller texture instead of the full resolution one.
+///
+/// Add it to a [`PostProcessCamera`](crate::PostProcessCamera), for heavy dimensions which are
+/// shown blurred anyway, like a backdrop. Each texel is the average of the `factor` x `factor`
+/// texels it covers, so the downscaled dimension is also softer.
+///
+/// Only available with the `pre_downscale` feature. It is ignored with
+/// [`DimensionStorage::TextureArray`](crate::DimensionStorage::TextureArray), whose layers all have the same size.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct PreDownscale {
+    /// Index of the downscaled dimension in the [`Dimensions`] of the camera.
+    pub dimension: u32,
+    /// How many times smaller the downscaled texture is, on each axis. 1 doesn't downscale.
+    pub factor: u32,
+}
+
+impl Default for PreDownscale {
+    fn default() -> Self {
+        Self {
+            dimension: 0,
+            factor: 2,
+        }
+    }
+}
+
+/// Adds the compute node downscaling the [`PreDownscale`] dimension, to `graph`.
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin), which orders [`DOWNSCALE_NODE`]
+/// before its own node once that one is in the graph.
+pub(crate) struct DownscalePlugin {
+    pub(crate) graph: &'static str,
+}
+
+/// The name of the downscale node in the [`DownscalePlugin::graph`].
+pub(crate) const DOWNSCALE_NODE: &str = DownscaleNode::NAME;
+
+impl Plugin for DownscalePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            DOWNSCALE_SHADER_HANDLE,
+            "../assets/shaders/downscale.wgsl",
+            Shader::from_wgsl
+        );
+        app.register_type::<PreDownscale>()
+            .add_plugin(ExtractComponentPlugin::<PreDownscale>::default());
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .init_resource::<DownscaledDimensions>()
+            .add_systems(
+                Render,
+                prepare_downscaled_dimensions.in_set(RenderSet::Prepare),
+            );
+
+        // The missing graph is reported by the `PostProcessPlugin`.
+        let render_graph = render_app.world.resource::<RenderGraph>();
+        if render_graph.get_sub_graph(self.graph).is_none() {
+            return;
+        }
+        render_app.add_render_graph_node::<DownscaleNode>(self.graph, DownscaleNode::NAME);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<DownscalePipeline>();
+    }
+}
+
+/// The image of the [`PreDownscale`] dimension, and the factor to downscale it by.
+#[derive(Component, Clone)]
+struct ExtractedPreDownscale {
+    image: Handle<Image>,
+    factor: u32,
+}
+
+impl ExtractComponent for PreDownscale {
+    type Query = (&'static Self, &'static Dimensions);
+    type Filter = ();
+    type Out = ExtractedPreDownscale;
+
+    fn extract_component((downscale, dimensions): QueryItem<'_, Self::Query>) -> Option<Self::Out> {
+        if downscale.factor <= 1 {
+            return None;
+        }
+        let dimension = dimensions.dimensions.get(downscale.dimension as usize)?;
+        Some(ExtractedPreDownscale {
+            // Weak handle, the main world keeps the image alive.
+            image: dimension.image.clone_weak(),
+            factor: downscale.factor,
+        })
+    }
+}
+
+/// The downscaled textures, per view.
+#[derive(Resource, Default)]
+struct DownscaledDimensions(HashMap<Entity, DownscaledDimension>);
+
+struct DownscaledDimension {
+    /// The downscaled image, the post process binds the view in its place.
+    image: Handle<Image>,
+    view: TextureView,
+    size: UVec2,
+}
+
+/// The view of the downscaled texture of `view_entity` and the image it replaces, if any.
+pub(crate) fn downscaled_view(
+    world: &World,
+    view_entity: Entity,
+) -> Option<(&Handle<Image>, &TextureView)> {
+    let downscaled = world
+        .get_resource::<DownscaledDimensions>()?
+        .0
+        .get(&view_entity)?;
+    Some((&downscaled.image, &downscaled.view))
+}
+
+/// Creates the downscaled textures, and recreates them when the size of their dimension changes.
+fn prepare_downscaled_dimensions(
+    mut downscaled: ResMut<DownscaledDimensions>,
+    views: Query<(Entity, &ExtractedPreDownscale)>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    downscaled.0.retain(|entity, _| views.contains(*entity));
+    for (entity, downscale) in views.iter() {
+        let Some(image) = gpu_images.get(&downscale.image) else {
+            downscaled.0.remove(&entity);
+            continue;
+        };
+        let size = (image.size.as_uvec2() / downscale.factor).max(UVec2::ONE);
+        if let Some(texture) = downscaled.0.get_mut(&entity) {
+            if texture.size == size {
+                texture.image = downscale.image.clone_weak();
+                continue;
+            }
+        }
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("post_process_downscaled_dimension"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            // The dimension images can't be storage textures, a float format keeps the precision of the average.
+            format: TextureFormat::Rgba16Float,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+            view_formats: &[],
+        });
+        downscaled.0.insert(
+            entity,
+            DownscaledDimension {
+                image: downscale.image.clone_weak(),
+                view: texture.create_view(&TextureViewDescriptor::default()),
+                size,
+            },
+        );
+    }
+}
+
+/// Runs the downscale compute pass of the views with a [`PreDownscale`].
+struct DownscaleNode {
+    query: QueryState<&'static ExtractedPreDownscale>,
+}
+
+impl DownscaleNode {
+    pub const NAME: &str = "post_process_downscale";
+}
+
+impl FromWorld for DownscaleNode {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(world),
+        }
+    }
+}
+
+impl Node for DownscaleNode {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph_context: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let view_entity = graph_context.view_entity();
+        let Ok(downscale) = self.query.get_manual(world, view_entity) else {
+            return Ok(());
+        };
+        let Some(downscaled) = world.resource::<DownscaledDimensions>().0.get(&view_entity) else {
+            return Ok(());
+        };
+        let Some(image) = world.resource::<RenderAssets<Image>>().get(&downscale.image) else {
+            return Ok(());
+        };
+        // Multisampled images can't be bound as `texture_2d`.
+        if image.texture.sample_count() != 1 {
+            return Ok(());
+        }
+        let downscale_pipeline = world.resource::<DownscalePipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_compute_pipeline(downscale_pipeline.pipeline_id) else {
+            return Ok(());
+        };
+
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("post_process_downscale_bind_group"),
+                layout: &downscale_pipeline.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&image.texture_view),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&downscaled.view),
+                    },
+                ],
+            });
+
+        let mut compute_pass =
+            render_context
+                .command_encoder()
+                .begin_compute_pass(&ComputePassDescriptor {
+                    label: Some("post_process_downscale_pass"),
+                });
+        compute_pass.set_pipeline(pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups(
+            (downscaled.size.x + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            (downscaled.size.y + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+            1,
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct DownscalePipeline {
+    layout: BindGroupLayout,
+    pipeline_id: CachedComputePipelineId,
+}
+
+impl FromWorld for DownscalePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_downscale_bind_group_layout"),
+            entries: &[
+                // The dimension texture
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                // The downscaled texture
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: TextureFormat::Rgba16Float,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_id =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_compute_pipeline(ComputePipelineDescriptor {
+                    label: Some("post_process_downscale_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    push_constant_ranges: vec![],
+                    shader: DOWNSCALE_SHADER_HANDLE.typed(),
+                    shader_defs: vec![],
+                    entry_point: "downscale".into(),
+                });
+
+        Self {
+            layout,
+            pipeline_id,
+        }
+    }
+}
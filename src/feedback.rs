@@ -0,0 +1,311 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::{ExtractedCamera, Viewport},
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType,
+            BufferInitDescriptor, BufferUsages, CachedRenderPipelineId, ColorTargetState,
+            ColorWrites, Extent3d, FragmentState, MultisampleState, Operations, PipelineCache,
+            PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+            RenderPipelineDescriptor, ShaderStages, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+            TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewTarget},
+        Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+
+use crate::PostProcessSettings;
+
+const FEEDBACK_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 2_718_554_093_640_172_449);
+
+/// Blends the previous frames over the composite of the views with [`PostProcessSettings::feedback`].
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub(crate) struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            FEEDBACK_SHADER_HANDLE,
+            "../assets/shaders/feedback.wgsl",
+            Shader::from_wgsl
+        );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.add_systems(Render, prepare_feedback_histories.in_set(RenderSet::Prepare));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<FeedbackHistories>();
+    }
+}
+
+/// The histories of the views with feedback, and the pipelines blending them, one per view target format.
+#[derive(Resource)]
+struct FeedbackHistories {
+    layout: BindGroupLayout,
+    pipelines: HashMap<TextureFormat, CachedRenderPipelineId>,
+    histories: HashMap<Entity, FeedbackHistory>,
+}
+
+/// The output of the previous frame of a view, in one of two textures: each frame reads one and writes the other.
+pub(crate) struct FeedbackHistory {
+    views: [TextureView; 2],
+    /// Index in `views` of the texture read this frame.
+    read: usize,
+    size: UVec2,
+    format: TextureFormat,
+    pipeline_id: CachedRenderPipelineId,
+    /// Whether the textures were just created: they are empty, there is nothing to blend yet.
+    fresh: bool,
+}
+
+impl FromWorld for FeedbackHistories {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let texture_entry = |binding| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: false },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        };
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_feedback_bind_group_layout"),
+            entries: &[
+                // The composite of this frame
+                texture_entry(0),
+                // The history
+                texture_entry(1),
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        Self {
+            layout,
+            pipelines: HashMap::default(),
+            histories: HashMap::default(),
+        }
+    }
+}
+
+/// Creates the history textures at the size and format of their view target, and swaps the texture
+/// read with the one written every frame.
+///
+/// The textures of the views without feedback anymore are dropped.
+fn prepare_feedback_histories(
+    mut feedback_histories: ResMut<FeedbackHistories>,
+    views: Query<(Entity, &PostProcessSettings, &ExtractedView, &ExtractedCamera)>,
+    render_device: Res<RenderDevice>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+) {
+    let feedback_histories = &mut *feedback_histories;
+    let mut used = Vec::new();
+    for (entity, settings, view, camera) in views.iter() {
+        if settings.feedback <= 0.0 {
+            continue;
+        }
+        let Some(size) = camera.physical_target_size else {
+            continue;
+        };
+        used.push(entity);
+        // The format of the view target main textures.
+        let format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        if let Some(history) = feedback_histories.histories.get_mut(&entity) {
+            if history.size == size && history.format == format {
+                history.read = 1 - history.read;
+                history.fresh = false;
+                continue;
+            }
+        }
+
+        let pipeline_id = *feedback_histories
+            .pipelines
+            .entry(format)
+            .or_insert_with(|| {
+                let target = Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                });
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("post_process_feedback_pipeline".into()),
+                    layout: vec![feedback_histories.layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: FEEDBACK_SHADER_HANDLE.typed(),
+                        shader_defs: vec![],
+                        entry_point: "feedback".into(),
+                        // The view target, then the history.
+                        targets: vec![target.clone(), target],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+            });
+        let create_view = || {
+            render_device
+                .create_texture(&TextureDescriptor {
+                    label: Some("post_process_feedback_history"),
+                    size: Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        };
+        feedback_histories.histories.insert(
+            entity,
+            FeedbackHistory {
+                views: [create_view(), create_view()],
+                read: 0,
+                size,
+                format,
+                pipeline_id,
+                fresh: true,
+            },
+        );
+    }
+    feedback_histories
+        .histories
+        .retain(|entity, _| used.contains(entity));
+}
+
+/// The history of `view_entity`, `None` if it has no feedback or until the pipeline is ready.
+pub(crate) fn feedback_history(world: &World, view_entity: Entity) -> Option<&FeedbackHistory> {
+    let history = world
+        .get_resource::<FeedbackHistories>()?
+        .histories
+        .get(&view_entity)?;
+    world
+        .resource::<PipelineCache>()
+        .get_render_pipeline(history.pipeline_id)?;
+    Some(history)
+}
+
+impl FeedbackHistory {
+    /// Blends the history over the composite in `view_target`, within the camera `viewport` if any,
+    /// and keeps the result as the history of the next frame.
+    ///
+    /// The composite must be the current main texture: this is a post process write of its own,
+    /// the result becomes the main texture.
+    pub(crate) fn apply(
+        &self,
+        world: &World,
+        render_context: &mut RenderContext,
+        view_target: &ViewTarget,
+        feedback: f32,
+        viewport: Option<&Viewport>,
+    ) {
+        let feedback_histories = world.resource::<FeedbackHistories>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(self.pipeline_id)
+        else {
+            return;
+        };
+        // Empty textures would fade the first frames from transparent black.
+        let feedback = if self.fresh {
+            0.0
+        } else {
+            feedback.clamp(0.0, 1.0)
+        };
+        // A single `f32`, padded to the 16 bytes of a uniform.
+        let contents: Vec<u8> = [feedback, 0.0, 0.0, 0.0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        let uniform = render_context
+            .render_device()
+            .create_buffer_with_data(&BufferInitDescriptor {
+                label: Some("post_process_feedback_uniform"),
+                contents: &contents,
+                usage: BufferUsages::UNIFORM,
+            });
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("post_process_feedback_bind_group"),
+                layout: &feedback_histories.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(post_process.source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&self.views[self.read]),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: uniform.as_entire_binding(),
+                    },
+                ],
+            });
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_process_feedback_pass"),
+            color_attachments: &[
+                Some(RenderPassColorAttachment {
+                    view: post_process.destination,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                }),
+                Some(RenderPassColorAttachment {
+                    view: &self.views[1 - self.read],
+                    resolve_target: None,
+                    ops: Operations::default(),
+                }),
+            ],
+            depth_stencil_attachment: None,
+        });
+        if let Some(viewport) = viewport {
+            render_pass.set_camera_viewport(viewport);
+        }
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
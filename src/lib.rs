@@ -0,0 +1,73 @@
+//! Renders several "dimensions" to textures and composites them with a post process.
+//!
+//! Each dimension is rendered by its own camera, see [`DimensionBuilder`] or [`create_camera`],
+//! and listed in the [`Dimensions`] of the main pass camera, which also needs a [`PostProcessCamera`].
+//! Add the [`PostProcessPlugin`] to run the post process.
+//!
+//! The post process shader is embedded in the crate, no asset needs to be copied.
+//!
+//! `use bevy_post_process_2_input_image::prelude::*;` brings in the plugins, components and helpers
+//! most apps need, the systems are only exported at the root for ordering against them.
+
+mod camera;
+mod diagnostic;
+mod dimension_builder;
+mod dimension_shader;
+mod dimensions;
+#[cfg(feature = "pre_downscale")]
+mod downscale;
+mod feedback;
+mod mipmaps;
+mod offscreen;
+mod picking;
+mod post_process;
+mod readback;
+mod static_frame;
+mod transition;
+
+pub use camera::{
+    apply_freeze_dimensions, apply_render_frequency, create_camera, create_depth_camera,
+    create_mipmapped_render_target, create_render_target, create_stacked_cameras,
+    sync_dimension_camera_transforms, FreezeDimensions, RenderFrequency, RequestDimensionRender,
+    SyncDimensionCameraTransform, DIMENSION_CAMERA_ORDER,
+};
+pub use diagnostic::PostProcessDiagnosticsPlugin;
+pub use dimension_builder::{BuiltDimension, DimensionBuilder, DimensionLayers, OutOfRenderLayers};
+pub use dimensions::{
+    apply_switch_dimension, auto_cycle_system, next_selected, on_resize_system, prev_selected,
+    resize_dimensions, AutoCycle, DimensionDef, DimensionSource, DimensionViewOffset, Dimensions,
+    ForegroundDimensions, PeekDimension, ResizeDebounce, ResizePolicy, SwitchDimension,
+};
+#[cfg(feature = "pre_downscale")]
+pub use downscale::PreDownscale;
+pub use post_process::{
+    BlendSpace, CaptureTarget, CompositeBlend, CompositeLayout, CompositeLoad, DimensionStorage,
+    PostProcessCamera, PostProcessConfig, PostProcessEnabled, PostProcessPlugin,
+    PostProcessSettings, RenderWorldDimensions, ScalingMode, TonemapMode, MAX_TEXTURE_COUNT,
+    POST_PROCESS_NODE,
+};
+#[doc(hidden)]
+pub use post_process::extract_dimensions_bench;
+pub use offscreen::render_composite_offscreen;
+pub use picking::{DimensionHit, DimensionPicking};
+pub use readback::{DimensionReadbackPlugin, DimensionSnapshot, ReadbackRequest};
+pub use transition::{
+    update_flashes, update_transitions, DimensionTransition, FlashOnSwitch, TransitionKind,
+    TransitionPhase,
+};
+
+pub mod prelude {
+    #[cfg(feature = "pre_downscale")]
+    pub use crate::PreDownscale;
+    pub use crate::{
+        create_camera, create_depth_camera, create_stacked_cameras, next_selected, prev_selected,
+        resize_dimensions, AutoCycle, BlendSpace, CaptureTarget, CompositeBlend, CompositeLayout,
+        CompositeLoad, DimensionBuilder, DimensionDef, DimensionLayers, DimensionPicking,
+        DimensionReadbackPlugin, DimensionSnapshot, DimensionSource, DimensionStorage,
+        DimensionTransition, DimensionViewOffset, Dimensions, FlashOnSwitch, ForegroundDimensions,
+        FreezeDimensions, PeekDimension, PostProcessCamera, PostProcessDiagnosticsPlugin,
+        PostProcessEnabled, PostProcessPlugin, PostProcessSettings, ReadbackRequest,
+        RenderFrequency, RequestDimensionRender, ResizeDebounce, ResizePolicy, ScalingMode,
+        SwitchDimension, SyncDimensionCameraTransform, TonemapMode, TransitionKind,
+    };
+}
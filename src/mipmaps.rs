@@ -0,0 +1,217 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntry, BindingResource, BindingType, CachedRenderPipelineId,
+            ColorTargetState, ColorWrites, FilterMode, FragmentState, LoadOp, MultisampleState,
+            Operations, PipelineCache, PrimitiveState, RenderPassColorAttachment,
+            RenderPassDescriptor, RenderPipelineDescriptor, Sampler, SamplerBindingType,
+            SamplerDescriptor, ShaderStages, TextureFormat, TextureSampleType, TextureUsages,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::GpuImage,
+        Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+
+use crate::post_process::ExtractedDimensions;
+
+const MIPMAP_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 9_862_417_305_118_643_227);
+
+/// Generates the mips of the bound dimension images which have some, for `DimensionDef::lod_bias`.
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub(crate) struct MipmapPlugin;
+
+impl Plugin for MipmapPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            MIPMAP_SHADER_HANDLE,
+            "../assets/shaders/mipmap.wgsl",
+            Shader::from_wgsl
+        );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.add_systems(Render, prepare_mipmap_pipelines.in_set(RenderSet::Prepare));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<MipmapPipelines>();
+    }
+}
+
+/// The pipelines generating a mip level from the previous one, one per texture format.
+#[derive(Resource)]
+struct MipmapPipelines {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipelines: HashMap<TextureFormat, CachedRenderPipelineId>,
+}
+
+impl FromWorld for MipmapPipelines {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_mipmap_bind_group_layout"),
+            entries: &[
+                // The previous mip level
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+        Self {
+            layout,
+            sampler,
+            pipelines: HashMap::default(),
+        }
+    }
+}
+
+/// Queues the pipelines for the formats of the bound dimension images with mips.
+fn prepare_mipmap_pipelines(
+    mut mipmaps: ResMut<MipmapPipelines>,
+    views: Query<&ExtractedDimensions>,
+    gpu_images: Res<RenderAssets<Image>>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+) {
+    let mipmaps = &mut *mipmaps;
+    for dimension in views.iter().flat_map(|dimensions| dimensions.slots.iter().flatten()) {
+        let Some(image) = gpu_images.get(&dimension.image) else {
+            continue;
+        };
+        if image.mip_level_count <= 1 {
+            continue;
+        }
+        mipmaps
+            .pipelines
+            .entry(image.texture_format)
+            .or_insert_with(|| {
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("post_process_mipmap_pipeline".into()),
+                    layout: vec![mipmaps.layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: MIPMAP_SHADER_HANDLE.typed(),
+                        shader_defs: vec![],
+                        entry_point: "downsample".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: image.texture_format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+            });
+    }
+}
+
+/// Renders each mip level of `image` from the previous one, the first level being the rendered dimension.
+///
+/// Does nothing for images without mips or which can't be rendered to, like the
+/// [`DimensionSource::External`](crate::DimensionSource::External) ones, or until the pipeline of their format is ready.
+pub(crate) fn generate_mipmaps(
+    world: &World,
+    render_context: &mut RenderContext,
+    image: &GpuImage,
+) {
+    if image.mip_level_count <= 1
+        || !image
+            .texture
+            .usage()
+            .contains(TextureUsages::RENDER_ATTACHMENT)
+    {
+        return;
+    }
+    let Some(mipmaps) = world.get_resource::<MipmapPipelines>() else {
+        return;
+    };
+    let Some(pipeline) = mipmaps
+        .pipelines
+        .get(&image.texture_format)
+        .and_then(|id| world.resource::<PipelineCache>().get_render_pipeline(*id))
+    else {
+        return;
+    };
+
+    let level_view = |level| {
+        image.texture.create_view(&TextureViewDescriptor {
+            label: Some("post_process_mip_level"),
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..default()
+        })
+    };
+    for level in 1..image.mip_level_count {
+        let source = level_view(level - 1);
+        let destination = level_view(level);
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("post_process_mipmap_bind_group"),
+                layout: &mipmaps.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&mipmaps.sampler),
+                    },
+                ],
+            });
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_process_mipmap_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: &destination,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::NONE.into()),
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
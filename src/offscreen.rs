@@ -0,0 +1,137 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    prelude::*,
+    render::{camera::RenderTarget, render_resource::Extent3d, view::RenderLayers},
+};
+
+use crate::{
+    camera::resize_render_target, create_render_target, DimensionSnapshot, Dimensions,
+    ForegroundDimensions, PostProcessCamera, PostProcessSettings, ReadbackRequest,
+};
+
+/// Frames rendered before the composite is read back, so the pipelines and bind groups
+/// of the new targets are ready.
+const WARMUP_FRAMES: u32 = 2;
+
+/// Frames waited for the readback before giving up.
+const MAX_READBACK_FRAMES: u32 = 60;
+
+/// Renders the composite of the post process `camera` into an image of `size`, independently of the window,
+/// and returns its pixels. For screenshots at a higher resolution than the window, e.g. promotional ones.
+///
+/// This is a one-shot operation which blocks until the pixels are read back: it runs `app.update()`
+/// for a few frames, so call it from outside of the schedule, not from a system.
+/// During those frames:
+/// - the dimension images of `camera`, its foreground ones included, are resized to `size`,
+///   whatever their [`ResizePolicy`](crate::ResizePolicy), and resized back afterwards.
+///   Their content is cleared by the resizes, their cameras render it again on the next frame.
+/// - a temporary 2d camera renders the composite into a new image of `size`, with the transform,
+///   projection, render layers, [`Dimensions`] and [`PostProcessSettings`] of `camera`.
+///   Transitions, peeks and view offsets are not copied, the selected dimensions are captured as is.
+///
+/// Returns `None` if `camera` is not a [`PostProcessCamera`] with `Dimensions`,
+/// or if the pixels are not read back after a few frames.
+/// Requires the [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub fn render_composite_offscreen(
+    app: &mut App,
+    camera: Entity,
+    size: Extent3d,
+) -> Option<DimensionSnapshot> {
+    let size = Extent3d {
+        width: size.width.max(1),
+        height: size.height.max(1),
+        depth_or_array_layers: 1,
+    };
+    let world = &mut app.world;
+    let entity = world.get_entity(camera)?;
+    entity.get::<PostProcessCamera>()?;
+    let dimensions = entity.get::<Dimensions>()?.clone();
+    let foreground = entity.get::<ForegroundDimensions>().cloned();
+    let settings = entity.get::<PostProcessSettings>().copied().unwrap_or_default();
+    let camera_component = entity.get::<Camera>().cloned().unwrap_or_default();
+    let camera_2d = entity.get::<Camera2d>().cloned().unwrap_or_else(|| Camera2d {
+        clear_color: ClearColorConfig::Default,
+    });
+    let projection = entity
+        .get::<OrthographicProjection>()
+        .cloned()
+        .unwrap_or_default();
+    let transform = entity
+        .get::<GlobalTransform>()
+        .map_or(Transform::default(), |transform| transform.compute_transform());
+    let render_layers = entity.get::<RenderLayers>().copied().unwrap_or_default();
+
+    // Resize the dimension images, keeping their sizes to restore them.
+    let mut images = world.resource_mut::<Assets<Image>>();
+    let mut original_sizes = vec![];
+    for dimension in dimensions
+        .dimensions
+        .iter()
+        .chain(foreground.iter().flat_map(|foreground| foreground.dimensions.iter()))
+    {
+        let Some(image) = images.get_mut(&dimension.image) else {
+            continue;
+        };
+        original_sizes.push((dimension.image.clone_weak(), image.texture_descriptor.size));
+        resize_render_target(image, size);
+    }
+    let target = create_render_target(size, &mut images);
+
+    let mut offscreen = world.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Image(target.clone()),
+                viewport: None,
+                is_active: true,
+                ..camera_component
+            },
+            camera_2d,
+            projection,
+            transform,
+            ..default()
+        },
+        render_layers,
+        dimensions,
+        settings,
+        PostProcessCamera,
+    ));
+    if let Some(foreground) = foreground {
+        offscreen.insert(foreground);
+    }
+    let offscreen = offscreen.id();
+
+    for _ in 0..WARMUP_FRAMES {
+        app.update();
+    }
+    let mut reader = app
+        .world
+        .resource::<Events<DimensionSnapshot>>()
+        .get_reader_current();
+    app.world.send_event(ReadbackRequest {
+        image: target.clone(),
+    });
+    let mut snapshot = None;
+    for _ in 0..MAX_READBACK_FRAMES {
+        app.update();
+        snapshot = reader
+            .iter(app.world.resource::<Events<DimensionSnapshot>>())
+            .find(|snapshot| snapshot.image == target)
+            .cloned();
+        if snapshot.is_some() {
+            break;
+        }
+    }
+    if snapshot.is_none() {
+        warn!("render_composite_offscreen: the composite was not read back after {MAX_READBACK_FRAMES} frames");
+    }
+
+    app.world.despawn(offscreen);
+    let mut images = app.world.resource_mut::<Assets<Image>>();
+    images.remove(&target);
+    for (image, size) in original_sizes {
+        if let Some(image) = images.get_mut(&image) {
+            resize_render_target(image, size);
+        }
+    }
+    snapshot
+}
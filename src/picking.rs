@@ -0,0 +1,110 @@
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{camera::RenderTarget, primitives::Aabb, view::RenderLayers},
+};
+
+use crate::{Dimensions, PostProcessCamera};
+
+/// Finds what is shown at a position of the composite, through the dimension it shows.
+///
+/// Only the displayed dimension of the `Dimensions` is picked, whatever the `CompositeLayout`:
+/// the dimension transitioned from, the foreground dimensions and the screen distortions,
+/// like `PostProcessSettings::curvature`, are not taken into account.
+#[derive(SystemParam)]
+pub struct DimensionPicking<'w, 's> {
+    composites: Query<'w, 's, (&'static Camera, &'static Dimensions), With<PostProcessCamera>>,
+    cameras: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Camera,
+            &'static GlobalTransform,
+            Option<&'static RenderLayers>,
+        ),
+    >,
+    pickables: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Aabb,
+            &'static GlobalTransform,
+            &'static ComputedVisibility,
+            Option<&'static RenderLayers>,
+        ),
+    >,
+}
+
+/// What [`DimensionPicking::pick`] found.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionHit {
+    /// Index of the displayed dimension in `Dimensions::dimensions`.
+    pub dimension: u32,
+    /// The camera rendering the dimension.
+    pub camera: Entity,
+    /// The picked position, in the world of the dimension camera.
+    pub world_position: Vec2,
+    /// The front-most visible entity of the dimension at `world_position`, by its bounding box.
+    /// `None` on the background of the dimension.
+    pub entity: Option<Entity>,
+}
+
+impl<'w, 's> DimensionPicking<'w, 's> {
+    /// Picks the displayed dimension of the [`PostProcessCamera`] `composite` at `viewport_position`,
+    /// in logical pixels from the top left corner of its viewport, like `Window::cursor_position`
+    /// for a camera covering the window.
+    ///
+    /// Returns `None` outside of the viewport or of the dimension image, or when the camera rendering
+    /// the dimension is not found: it is found by its render target, the dimension `image`.
+    pub fn pick(&self, composite: Entity, viewport_position: Vec2) -> Option<DimensionHit> {
+        let (composite_camera, dimensions) = self.composites.get(composite).ok()?;
+        let viewport_size = composite_camera.logical_viewport_size()?;
+        let uv = viewport_position / viewport_size;
+        if uv.cmplt(Vec2::ZERO).any() || uv.cmpgt(Vec2::ONE).any() {
+            return None;
+        }
+        let index = dimensions.displayed_index();
+        let dimension = dimensions.dimensions.get(index as usize)?;
+        let image_uv = dimension.image_uv(uv);
+        if image_uv.cmplt(Vec2::ZERO).any() || image_uv.cmpgt(Vec2::ONE).any() {
+            return None;
+        }
+
+        let (camera_entity, camera, camera_transform, camera_layers) =
+            self.cameras.iter().find(|(_, camera, _, _)| {
+                matches!(&camera.target, RenderTarget::Image(image) if *image == dimension.image)
+            })?;
+        let image_size = camera.logical_viewport_size()?;
+        let world_position = camera.viewport_to_world_2d(camera_transform, image_uv * image_size)?;
+
+        let camera_layers = camera_layers.copied().unwrap_or_default();
+        let entity = self
+            .pickables
+            .iter()
+            .filter(|(_, _, _, visibility, layers)| {
+                visibility.is_visible()
+                    && camera_layers.intersects(&layers.copied().unwrap_or_default())
+            })
+            .filter(|(_, aabb, transform, _, _)| {
+                // In the space of the entity, at its depth.
+                let point = world_position.extend(transform.translation().z);
+                let local = transform.affine().inverse().transform_point3(point);
+                let distance = (Vec3::from(aabb.center) - local).abs();
+                distance.x <= aabb.half_extents.x && distance.y <= aabb.half_extents.y
+            })
+            // The closest to the 2d camera, which looks down the z axis.
+            .max_by(|(_, _, a, _, _), (_, _, b, _, _)| {
+                a.translation().z.total_cmp(&b.translation().z)
+            })
+            .map(|(entity, ..)| entity);
+
+        Some(DimensionHit {
+            dimension: index,
+            camera: camera_entity,
+            world_position,
+            entity,
+        })
+    }
+}
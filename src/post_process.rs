@@ -1,52 +1,401 @@
 use std::num::NonZeroU32;
 
 use bevy::core_pipeline::core_2d;
+use bevy::ecs::query::QueryItem;
 use bevy::prelude::*;
 
 use bevy::render::globals::{GlobalsBuffer, GlobalsUniform};
 use bevy::render::render_asset::RenderAssets;
 use bevy::render::render_resource::{AsBindGroup, BufferBindingType, UniformBuffer};
 use bevy::render::texture::GpuImage;
+use bevy::transform::TransformSystem;
+use bevy::utils::{HashMap, HashSet};
+use bevy::{asset::load_internal_asset, reflect::TypeUuid};
 use bevy::{
-    asset::ChangeWatcher,
     core_pipeline::{
         clear_color::ClearColorConfig, core_3d,
         fullscreen_vertex_shader::fullscreen_shader_vertex_state,
     },
     prelude::*,
     render::{
+        camera::{ExtractedCamera, NormalizedRenderTarget, RenderTarget},
         extract_component::{
-            ComponentUniforms, ExtractComponent, ExtractComponentPlugin, UniformComponentPlugin,
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
         },
-        render_graph::{Node, NodeRunError, RenderGraphApp, RenderGraphContext},
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_graph::{Node, NodeRunError, RenderGraph, RenderGraphApp, RenderGraphContext},
         render_resource::{
-            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
-            BindGroupLayoutEntry, BindingResource, BindingType, CachedRenderPipelineId,
-            ColorTargetState, ColorWrites, FragmentState, MultisampleState, Operations,
+            AddressMode, BindGroupDescriptor, BindGroupEntry, BindGroupLayout,
+            BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingResource, BindingType,
+            BlendComponent, BlendFactor, BlendOperation, BlendState, CachedPipelineState,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, Extent3d, FilterMode,
+            FragmentState, ImageCopyTexture, LoadOp, MultisampleState, Operations, Origin3d,
             PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
-            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
-            ShaderType, TextureFormat, TextureSampleType, TextureViewDimension,
+            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderDefVal,
+            ShaderStages, ShaderType, Texture, TextureAspect, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureFormatFeatureFlags, TextureSampleType, TextureUsages,
+            TextureView, TextureViewDescriptor, TextureViewDimension,
         },
-        renderer::{RenderContext, RenderDevice},
+        renderer::{RenderAdapter, RenderContext, RenderDevice, RenderQueue},
+        settings::WgpuFeatures,
         texture::BevyDefault,
-        view::{ExtractedView, ViewTarget},
-        RenderApp,
+        view::{ExtractedView, ViewDepthTexture, ViewTarget, VisibilitySystems, VisibleEntities},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
     },
-    utils::Duration,
 };
 
-use crate::{Dimensions};
+use crate::{
+    apply_freeze_dimensions, apply_render_frequency, apply_switch_dimension, auto_cycle_system,
+    diagnostic::PassTimestamps,
+    dimension_shader::{shade_dimension, DimensionShaderPlugin},
+    feedback::{feedback_history, FeedbackPlugin},
+    mipmaps::{generate_mipmaps, MipmapPlugin},
+    on_resize_system,
+    static_frame::{static_composite, StaticFramePlugin},
+    sync_dimension_camera_transforms, update_flashes, update_transitions, AutoCycle, DimensionDef,
+    DimensionLayers, DimensionReadbackPlugin, DimensionSource, DimensionTransition,
+    DimensionViewOffset, Dimensions, FlashOnSwitch, ForegroundDimensions, FreezeDimensions,
+    PeekDimension, RenderFrequency, RequestDimensionRender, ResizeDebounce, ResizePolicy,
+    SwitchDimension, SyncDimensionCameraTransform, TransitionKind,
+};
+#[cfg(feature = "pre_downscale")]
+use crate::downscale::{downscaled_view, DownscalePlugin, DOWNSCALE_NODE};
+
+/// The post process shader, embedded in the crate so downstream users don't need to copy it in their assets.
+const POST_PROCESSING_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 5_163_809_725_334_082_451);
+/// The default `bevy_post_process_2_input_image::composite` shader import, see [`PostProcessPlugin::composite_shader`].
+const COMPOSITE_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 12_458_830_917_644_305_291);
 
 /// It is generally encouraged to set up post processing effects as a plugin
-pub struct PostProcessPlugin;
+///
+/// By default, the post process runs in the 2d graph, between tonemapping and the end of the post processing.
+/// To order it relatively to other effects such as bloom or FXAA, set the neighbor nodes:
+/// the post process node runs after `after` and before `before`, which must both exist in `graph`.
+///
+/// The other fields are the initial [`PostProcessConfig`], which can be changed at runtime.
+pub struct PostProcessPlugin {
+    /// Name of the render graph the post process node is added to.
+    pub graph: &'static str,
+    /// Name of the node running right before the post process.
+    pub after: &'static str,
+    /// Name of the node running right after the post process.
+    pub before: &'static str,
+    /// How the dimension textures are sampled outside of 0..1 horizontally, clamped to the edge by default.
+    ///
+    /// UVs leave that range when `DimensionDef::uv_scale` and `uv_offset` move the texture:
+    /// use `AddressMode::Repeat` to tile a dimension, `AddressMode::MirrorRepeat` to mirror it.
+    pub address_mode_u: AddressMode,
+    /// Like `address_mode_u`, vertically.
+    pub address_mode_v: AddressMode,
+    /// The maximum anisotropy of the filtering with `ScalingMode::Linear`, `1` by default which disables it.
+    ///
+    /// Dimensions stretched by their `uv_scale` or `uv_matrix`, like a floor seen at an oblique angle,
+    /// stay sharp along their stretched axis with more samples: up to `anisotropy` per fetch, 16 at most.
+    /// Higher values cost more bandwidth on the stretched parts only. Devices without anisotropic filtering,
+    /// and non-filterable dimension textures, fall back to the plain filtering.
+    pub anisotropy: u16,
+    /// How many dimensions of each group, `Dimensions` and `ForegroundDimensions`, are bound to the shader,
+    /// 2 by default: the others are never shown. Clamped from 2 to [`MAX_TEXTURE_COUNT`].
+    ///
+    /// Each bound dimension takes a texture and a depth texture binding, which count against
+    /// the `max_sampled_textures_per_shader_stage` limit of the device. Without binding array support,
+    /// as on WebGL2, 2 dimensions are bound per group whatever this value.
+    pub max_textures: usize,
+    /// How the dimension textures are bound to the shader.
+    pub storage: DimensionStorage,
+    /// How the composited dimensions are blended onto the main pass of the camera.
+    pub blend: CompositeBlend,
+    /// Whether the shader binds bevy's `Globals`, `true` by default.
+    ///
+    /// Without them the post process doesn't depend on the `GlobalsBuffer` being available,
+    /// but `time()` is always 0 in the shader: the animated effects are frozen.
+    pub globals: bool,
+    /// Asset path of a shader replacing how the background dimensions are composited, `None` for the default one.
+    ///
+    /// It must start with `#define_import_path bevy_post_process_2_input_image::composite`
+    /// and define `composite_offset` and `composite_background`. It is resolved on its own: the bound background
+    /// dimensions are sampled by the post process shader and passed to it, it can't use the bindings.
+    /// The contract is documented in the default one, `assets/shaders/composite.wgsl` in this crate.
+    pub composite_shader: Option<&'static str>,
+    /// Asset path of a shader replacing the whole post process shader, `None` for the embedded one.
+    ///
+    /// It must use the same bindings as `assets/shaders/post_processing.wgsl` in this crate.
+    /// The copy of the main pass, see [`CompositeBlend`], keeps using the embedded shader.
+    pub shader: Option<&'static str>,
+    /// Name of the fragment entry point of the post process shader, `"fragment"` by default.
+    pub fragment_entry_point: &'static str,
+}
+
+/// How the composited dimensions are blended onto the main pass of a [`PostProcessCamera`].
+///
+/// The blending uses the alpha of the composite, which is the alpha of the dimension textures
+/// multiplied by `DimensionDef::alpha`: dimensions cleared to an opaque color hide the main pass.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum CompositeBlend {
+    /// Straight alpha blending: the main pass shows through transparent parts of the dimensions.
+    Alpha,
+    /// The dimensions are added to the main pass, weighted by their alpha, for ghostly overlays.
+    Additive,
+    /// The dimensions replace the main pass, alpha included, the default.
+    /// This skips copying the main pass, see [`CompositeBlend::blend_state`].
+    #[default]
+    None,
+}
+
+impl CompositeBlend {
+    /// The blend state of the composite pipeline.
+    ///
+    /// With blending, the main pass is copied to the destination texture first,
+    /// as the post process writes to a different texture than the one it reads.
+    pub fn blend_state(self) -> Option<BlendState> {
+        match self {
+            CompositeBlend::Alpha => Some(BlendState::ALPHA_BLENDING),
+            CompositeBlend::Additive => Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::SrcAlpha,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent::OVER,
+            }),
+            CompositeBlend::None => None,
+        }
+    }
+}
+
+/// How the dimension textures are bound to the post process shader.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DimensionStorage {
+    /// Each bound dimension texture has its own binding, in a binding array.
+    ///
+    /// Dimensions can have different sizes. When the GPU doesn't support binding arrays,
+    /// as on WebGL2, each texture is bound individually instead, this needs no configuration.
+    #[default]
+    BindingArray,
+    /// The bound dimension textures are copied into the layers of a single 2d array texture,
+    /// so a single texture binding is used whatever the number of dimensions.
+    ///
+    /// Cameras can't render into a layer, hence the copy, which costs some bandwidth every frame.
+    /// The layers have the size and format of the selected background dimension:
+    /// the other dimensions must share them, larger ones are cropped and ones of another format are not shown.
+    /// The dimension images need the `COPY_SRC` usage, which `create_render_target` sets.
+    TextureArray,
+}
+
+impl Default for PostProcessPlugin {
+    fn default() -> Self {
+        Self {
+            graph: core_2d::graph::NAME,
+            after: core_2d::graph::node::TONEMAPPING,
+            before: core_2d::graph::node::END_MAIN_PASS_POST_PROCESSING,
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            anisotropy: 1,
+            max_textures: 2,
+            storage: DimensionStorage::default(),
+            blend: CompositeBlend::default(),
+            globals: true,
+            composite_shader: None,
+            shader: None,
+            fragment_entry_point: "fragment",
+        }
+    }
+}
+
+impl PostProcessPlugin {
+    fn config(&self) -> PostProcessConfig {
+        PostProcessConfig {
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            anisotropy: self.anisotropy,
+            max_textures: self.max_textures,
+            storage: self.storage,
+            blend: self.blend,
+            globals: self.globals,
+        }
+    }
+
+    /// Whether the post process can run in this app: it needs a render app,
+    /// which headless setups without a renderer don't have.
+    pub fn is_supported(app: &App) -> bool {
+        app.get_sub_app(RenderApp).is_ok()
+    }
+}
 
 impl Plugin for PostProcessPlugin {
     fn build(&self, app: &mut App) {
+        // With the `hot_reload` feature, bevy's debug asset server reloads it when it changes on disk,
+        // the `PipelineCache` then recompiles the pipeline and the `pipeline_id` stays valid.
+        load_internal_asset!(
+            app,
+            POST_PROCESSING_SHADER_HANDLE,
+            "../assets/shaders/post_processing.wgsl",
+            Shader::from_wgsl
+        );
+        match self.composite_shader {
+            None => load_internal_asset!(
+                app,
+                COMPOSITE_SHADER_HANDLE,
+                "../assets/shaders/composite.wgsl",
+                Shader::from_wgsl
+            ),
+            // The post process shader imports it by its import path, the handle only keeps it loaded.
+            Some(path) => {
+                let composite_shader = app.world.resource::<AssetServer>().load(path);
+                app.insert_resource(CompositeShader(composite_shader));
+            }
+        }
+
+        // Registered for the inspectors, along with the types nested in the components.
+        app.register_type::<Dimensions>()
+            .register_type::<ForegroundDimensions>()
+            .register_type::<DimensionViewOffset>()
+            .register_type::<PeekDimension>()
+            .register_type::<DimensionDef>()
+            .register_type::<Vec<DimensionDef>>()
+            .register_type::<ResizePolicy>()
+            .register_type::<DimensionSource>()
+            .register_type::<AutoCycle>()
+            .register_type::<DimensionTransition>()
+            .register_type::<TransitionKind>()
+            .register_type::<FlashOnSwitch>()
+            .register_type::<RenderFrequency>()
+            .register_type::<SyncDimensionCameraTransform>()
+            .init_resource::<DimensionLayers>()
+            .init_resource::<ResizeDebounce>()
+            .init_resource::<FreezeDimensions>()
+            .insert_resource(self.config())
+            .add_plugin(ExtractResourcePlugin::<PostProcessConfig>::default())
+            .init_resource::<PostProcessEnabled>()
+            .add_plugin(ExtractResourcePlugin::<PostProcessEnabled>::default())
+            .add_plugin(DimensionReadbackPlugin)
+            .add_plugin(DimensionShaderPlugin)
+            .add_plugin(MipmapPlugin)
+            .add_plugin(StaticFramePlugin)
+            .add_plugin(FeedbackPlugin)
+            .add_event::<SwitchDimension>()
+            .add_event::<RequestDimensionRender>()
+            .add_plugin(ExtractComponentPlugin::<DimensionTransition>::default())
+            .add_systems(
+                Update,
+                (
+                    (
+                        auto_cycle_system,
+                        apply_switch_dimension,
+                        (update_transitions, update_flashes),
+                    )
+                        .chain(),
+                    on_resize_system,
+                ),
+            )
+            // After the requests of this frame are sent, and the images are resized.
+            .add_systems(
+                PostUpdate,
+                (apply_render_frequency, apply_freeze_dimensions).chain(),
+            )
+            // Between the transform propagation and the visibility computations which depend on it.
+            .add_systems(
+                PostUpdate,
+                sync_dimension_camera_transforms
+                    .after(TransformSystem::TransformPropagate)
+                    .before(VisibilitySystems::UpdateOrthographicFrusta)
+                    .before(VisibilitySystems::UpdatePerspectiveFrusta)
+                    .before(VisibilitySystems::UpdateProjectionFrusta),
+            );
+
+        // The per-dimension settings are computed when extracting the `Dimensions`,
+        // see `extract_post_process_cameras`, this plugin takes care of uploading them to the GPU.
+        app.add_plugin(UniformComponentPlugin::<DimensionsUniform>::default());
+
+        app.register_type::<PostProcessCamera>()
+            .register_type::<PostProcessSettings>()
+            .register_type::<ScalingMode>()
+            .register_type::<TonemapMode>()
+            .register_type::<BlendSpace>()
+            .register_type::<CompositeLoad>()
+            .register_type::<CompositeLayout>()
+            .register_type::<CaptureTarget>()
+            .add_plugin(ExtractComponentPlugin::<CaptureTarget>::default())
+            .add_systems(
+                Update,
+                (
+                    check_capture_targets,
+                    check_dimension_images,
+                    check_dimension_count,
+                    check_camera_order,
+                ),
+            )
+            // The uniform is the data used in the shader.
+            // This plugin will prepare the component for the GPU by creating a uniform buffer
+            // and writing the data to that buffer every frame.
+            .add_plugin(UniformComponentPlugin::<PostProcessUniform>::default());
+
+        // Before the early returns below, so the `PreDownscale` components are registered and extracted
+        // even when the post process can't run.
+        #[cfg(feature = "pre_downscale")]
+        app.add_plugin(DownscalePlugin { graph: self.graph });
+
+        #[cfg(debug_assertions)]
+        app.add_systems(Update, check_dimension_render_targets);
+
+        if self.fragment_entry_point.is_empty() {
+            error!("PostProcessPlugin: the fragment entry point is empty, it must name a `@fragment` function of the shader");
+        }
+        let shader = match self.shader {
+            Some(path) => app.world.resource::<AssetServer>().load(path),
+            None => POST_PROCESSING_SHADER_HANDLE.typed(),
+        };
+
         // We need to get the render app from the main app
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            warn!("PostProcessPlugin: no render app found (is the app headless?), the post process will not run");
             return;
         };
 
+        render_app
+            // The settings will be a component that lives in the main world but will
+            // be extracted to the render world every frame, as a `PostProcessUniform`,
+            // along with the `PostProcessCamera` marker.
+            .add_systems(ExtractSchedule, extract_post_process_cameras)
+            .insert_resource(PostProcessShader {
+                shader,
+                path: self.shader,
+                fragment_entry_point: self.fragment_entry_point,
+            })
+            .init_resource::<DimensionTextureArrays>()
+            .init_resource::<DimensionContent>()
+            .init_resource::<DimensionsFilterable>()
+            .add_systems(
+                Render,
+                (
+                    prepare_dimensions_filterable.before(prepare_post_process_pipeline),
+                    prepare_post_process_pipeline,
+                    prepare_dimension_texture_arrays,
+                    prepare_dimension_content,
+                    check_post_process_pipeline,
+                )
+                    .in_set(RenderSet::Prepare),
+            );
+
+        // Adding edges to missing nodes panics, report the misconfiguration instead.
+        let render_graph = render_app.world.resource::<RenderGraph>();
+        let Some(sub_graph) = render_graph.get_sub_graph(self.graph) else {
+            warn!("PostProcessPlugin: render graph {:?} not found, the post process will not run", self.graph);
+            return;
+        };
+        for node in [self.after, self.before] {
+            if sub_graph.get_node_state(node).is_err() {
+                warn!(
+                    "PostProcessPlugin: node {:?} not found in render graph {:?}, the post process will not run",
+                    node, self.graph
+                );
+                return;
+            }
+        }
+
         render_app
             // Bevy's renderer uses a render graph which is a collection of nodes in a directed acyclic graph.
             // It currently runs on each view/camera and executes each node in the specified order.
@@ -59,45 +408,63 @@ impl Plugin for PostProcessPlugin {
             // Add a [`Node`] to the [`RenderGraph`]
             // The Node needs to impl FromWorld
             .add_render_graph_node::<PostProcessNode>(
-                // Specifiy the name of the graph, in this case we want the graph for 2d
-                core_2d::graph::NAME,
+                // Specifiy the name of the graph, the graph for 2d by default
+                self.graph,
                 // It also needs the name of the node
                 PostProcessNode::NAME,
             )
             .add_render_graph_edges(
-                core_2d::graph::NAME,
+                self.graph,
                 // Specify the node ordering.
                 // This will automatically create all required node edges to enforce the given ordering.
-                &[
-                    core_2d::graph::node::TONEMAPPING,
-                    PostProcessNode::NAME,
-                    core_2d::graph::node::END_MAIN_PASS_POST_PROCESSING,
-                ],
+                &[self.after, PostProcessNode::NAME, self.before],
             );
+
+        #[cfg(feature = "pre_downscale")]
+        render_app.add_render_graph_edge(self.graph, DOWNSCALE_NODE, PostProcessNode::NAME);
     }
 
     fn finish(&self, app: &mut App) {
-        // We need to get the render app from the main app
+        // We need to get the render app from the main app,
+        // its absence has already been reported in `build`.
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
 
         render_app
+            // The pipeline needs the configuration before it is first extracted.
+            .insert_resource(self.config())
             // Initialize the pipeline
             .init_resource::<PostProcessPipeline>();
     }
 }
 
+/// The name of the post process node in the [`PostProcessPlugin::graph`], to order other nodes around it.
+pub const POST_PROCESS_NODE: &str = "post_process";
+
 /// The post process node used for the render graph
 struct PostProcessNode {
     // The node needs a query to gather data from the ECS in order to do its rendering,
     // but it's not a normal system so we need to define it manually.
-    query: QueryState<&'static ViewTarget, With<ExtractedView>>,
-    query_source: QueryState<&'static Dimensions>,
+    query: QueryState<
+        (
+            &'static ViewTarget,
+            Option<&'static ExtractedCamera>,
+            &'static PostProcessSettings,
+            &'static DynamicUniformIndex<PostProcessUniform>,
+            Option<&'static DimensionTransition>,
+        ),
+        (With<ExtractedView>, With<PostProcessCamera>),
+    >,
+    query_source: QueryState<(
+        &'static ExtractedDimensions,
+        &'static DynamicUniformIndex<DimensionsUniform>,
+    )>,
+    query_capture: QueryState<&'static CaptureTarget>,
 }
 
 impl PostProcessNode {
-    pub const NAME: &str = "post_process";
+    pub const NAME: &str = POST_PROCESS_NODE;
 }
 
 impl FromWorld for PostProcessNode {
@@ -105,6 +472,7 @@ impl FromWorld for PostProcessNode {
         Self {
             query: QueryState::new(world),
             query_source: QueryState::new(world),
+            query_capture: QueryState::new(world),
         }
     }
 }
@@ -118,6 +486,7 @@ impl Node for PostProcessNode {
         // For now, you can just copy it.
         self.query.update_archetypes(world);
         self.query_source.update_archetypes(world);
+        self.query_capture.update_archetypes(world);
     }
 
     // Runs the node logic
@@ -131,15 +500,28 @@ impl Node for PostProcessNode {
         render_context: &mut RenderContext,
         world: &World,
     ) -> Result<(), NodeRunError> {
+        // Disabled at runtime, the main pass is left untouched
+        if world
+            .get_resource::<PostProcessEnabled>()
+            .is_some_and(|enabled| !enabled.0)
+        {
+            return Ok(());
+        }
+
         // Get the entity of the view for the render graph where this node is running
         let view_entity = graph_context.view_entity();
 
-        // TODO: this is not used, but without it the textures are not filled... not sure why..?
-        let Ok(dimensions) = self.query_source.get_manual(world, view_entity) else {
+        // The dimensions extracted for this view: the images bound below, and the offset of their
+        // `DimensionsUniform` in the dynamic uniform buffer.
+        let Ok((dimensions, dimensions_uniform_index)) = self.query_source.get_manual(world, view_entity) else {
             return Ok(());
         };
-        //
-        let Ok(view_target_main) = self.query.get_manual(world, view_entity) else {
+        // Without any dimension there is nothing to composite, the main pass is left untouched
+        if dimensions.is_empty() {
+            return Ok(());
+        }
+        // Views without a `PostProcessCamera` are left untouched
+        let Ok((view_target_main, camera, settings, settings_index, transition)) = self.query.get_manual(world, view_entity) else {
             return Ok(());
         };
         // Get the pipeline resource that contains the global data we need to create the render pipeline
@@ -149,14 +531,58 @@ impl Node for PostProcessNode {
         // It is required to avoid creating a new pipeline each frame, which is expensive due to shader compilation.
         let pipeline_cache = world.resource::<PipelineCache>();
 
+        // HDR views have main textures of another format, which need their own pipelines
+        let (pipeline_id, copy_pipeline_id) = if view_target_main.is_hdr() {
+            (
+                post_process_pipeline.hdr_pipeline_id,
+                post_process_pipeline.hdr_copy_pipeline_id,
+            )
+        } else {
+            (
+                post_process_pipeline.pipeline_id,
+                post_process_pipeline.copy_pipeline_id,
+            )
+        };
+
         // Get the pipeline from the cache
-        let Some(pipeline) = pipeline_cache.get_render_pipeline(post_process_pipeline.pipeline_id) else {
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id) else {
             return Ok(());
         };
+        let config = world.resource::<PostProcessConfig>();
+        // Blending onto the main pass needs it in the destination texture first.
+        // So does discarding the transparent parts of overlays.
+        // A cleared composite pass doesn't need it.
+        let copy_pipeline = match config.blend {
+            _ if matches!(settings.composite_load, CompositeLoad::Clear(_)) => None,
+            CompositeBlend::None if settings.overlay_threshold <= 0.0 => None,
+            CompositeBlend::None | CompositeBlend::Alpha | CompositeBlend::Additive => {
+                let Some(copy_pipeline) = pipeline_cache.get_render_pipeline(copy_pipeline_id) else {
+                    return Ok(());
+                };
+                Some(copy_pipeline)
+            }
+        };
 
-        // Get the globals uniform binding
-        let globals_buffer = world.resource::<GlobalsBuffer>();
-        let Some(globals_binding) = globals_buffer.buffer.binding() else {
+        // Get the globals uniform binding, when the shader uses it
+        let globals_binding = if config.globals {
+            let globals_buffer = world.resource::<GlobalsBuffer>();
+            let Some(globals_binding) = globals_buffer.buffer.binding() else {
+                return Ok(());
+            };
+            Some(globals_binding)
+        } else {
+            None
+        };
+
+        // Get the per-dimension settings binding
+        let dimensions_uniforms = world.resource::<ComponentUniforms<DimensionsUniform>>();
+        let Some(dimensions_binding) = dimensions_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        // Get the settings uniform binding
+        let settings_uniforms = world.resource::<ComponentUniforms<PostProcessUniform>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
             return Ok(());
         };
 
@@ -168,33 +594,228 @@ impl Node for PostProcessNode {
         // texture to the `destination` texture. Failing to do so will cause
         // the current main texture information to be lost.
         let post_process_main = view_target_main.post_process_write();
+        let viewport = camera.and_then(|camera| camera.viewport.as_ref());
 
-        // TODO: Should I use the post_process_write or the references to the images ?
-       /*let Some(handle_dimensions) = world.get_resource::<Dimensions>() else {
+        // With `PostProcessSettings::skip_static_frames`, the composite is drawn into a texture kept
+        // between frames, which is copied to the destination. Static frames only copy it.
+        // Measured like the composite, to show the saving.
+        let static_composite =
+            static_composite(world, view_entity).filter(|_| copy_pipeline.is_none());
+        if let Some(static_composite) = static_composite.filter(|composite| !composite.dirty) {
+            let timestamps = world
+                .get_resource::<PassTimestamps>()
+                .filter(|timestamps| timestamps.begin(render_context));
+            let destination = post_process_main.destination;
+            static_composite.copy_to(world, render_context, destination, viewport);
+            if let Some(timestamps) = timestamps {
+                timestamps.end(render_context);
+            }
             return Ok(());
-        };*/
+        }
+
         let gpu_images = world.get_resource::<RenderAssets<Image>>().unwrap();
+        let dimension_content = world.resource::<DimensionContent>();
 
-        // retrieve the render resources from handles
-        let mut images = vec![];
-        for handle in dimensions.dimensions.iter().take(MAX_TEXTURE_COUNT) {
-            match gpu_images.get(&handle.image) {
-                Some(image) => images.push(image),
-                None => return Ok(()),
-            }
+        // retrieve the render resources from handles, in the order they are bound:
+        // the background group, then the foreground group, see `slot_image`.
+        // Multisampled images can't be bound as `texture_2d`, they are reported by `check_dimension_images`.
+        let images: Vec<_> = (0..dimensions.slots.len())
+            .map(|slot| {
+                slot_image(
+                    dimensions,
+                    slot,
+                    |image| dimension_content.has_content(image),
+                    |image| {
+                        gpu_images
+                            .get(image)
+                            .filter(|image| image.texture.sample_count() == 1)
+                    },
+                    &post_process_pipeline.fallback_image,
+                    &post_process_pipeline.missing_image,
+                )
+            })
+            .collect();
+
+        // The fallback and missing images have no mips, only the dimension images with some are generated.
+        for image in images.iter() {
+            generate_mipmaps(world, render_context, image);
         }
 
-        let mut textures = Vec::with_capacity(MAX_TEXTURE_COUNT);
+        let mut textures = Vec::with_capacity(dimensions.slots.len());
 
-        // fill in up to the first `MAX_TEXTURE_COUNT` textures and samplers to the arrays
-        for image in images
-            .iter()
-            .cycle()
-            .skip(dimensions.selected as usize)
-            .take(MAX_TEXTURE_COUNT.min(images.len()))
-        {
+        // fill in the `PostProcessPlugin::max_textures` textures of each group to the array
+        for image in images.iter() {
             textures.push(&*image.texture_view);
         }
+        // Dimensions with their own shader are sampled from the texture it renders to.
+        for (slot, dimension) in dimensions.slots.iter().enumerate() {
+            if config.storage != DimensionStorage::BindingArray
+                || dimensions.texture_slots[slot] != slot as u32
+            {
+                continue;
+            }
+            let Some((image, shader)) = dimension
+                .as_ref()
+                .and_then(|dimension| Some((&dimension.image, dimension.shader.as_ref()?)))
+            else {
+                continue;
+            };
+            if let Some(view) = shade_dimension(world, render_context, image, images[slot], shader) {
+                textures[slot] = view;
+            }
+        }
+        // The `PreDownscale` dimension is sampled from its downscaled texture, rather than its shaded one.
+        #[cfg(feature = "pre_downscale")]
+        if let (DimensionStorage::BindingArray, Some((image, view))) =
+            (config.storage, downscaled_view(world, view_entity))
+        {
+            for (slot, dimension) in dimensions.slots.iter().enumerate() {
+                if dimensions.texture_slots[slot] == slot as u32
+                    && dimension.as_ref().is_some_and(|dimension| &dimension.image == image)
+                {
+                    textures[slot] = view;
+                }
+            }
+        }
+        // The depth textures of the dimensions rendered by a 3d camera, for depth compositing.
+        // Other slots get the fallback depth texture, which is at the far plane.
+        let mut depth_textures = Vec::with_capacity(dimensions.slots.len());
+        for dimension in dimensions.slots.iter() {
+            let depth = dimension
+                .as_ref()
+                .and_then(|dimension| dimension.depth_camera)
+                .and_then(|camera| world.get::<ViewDepthTexture>(camera))
+                // Multisampled depth textures can't be bound as `texture_depth_2d`
+                .filter(|depth| depth.texture.sample_count() == 1);
+            depth_textures.push(match depth {
+                Some(depth) => &*depth.view,
+                None => &*post_process_pipeline.fallback_depth_view,
+            });
+        }
+
+        // With `DimensionStorage::TextureArray`, the images are copied into the layers of the view array texture.
+        let texture_array = match config.storage {
+            DimensionStorage::BindingArray => None,
+            DimensionStorage::TextureArray => {
+                let Some(array) = world.resource::<DimensionTextureArrays>().0.get(&view_entity) else {
+                    return Ok(());
+                };
+                Some(array)
+            }
+        };
+        if let Some(array) = texture_array {
+            for (layer, image) in images.iter().enumerate() {
+                // Clear the layer first, so empty and cropped layers don't show previous frames.
+                render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                    label: Some("post_process_clear_layer_pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: &array.layer_views[layer],
+                        resolve_target: None,
+                        ops: Operations {
+                            load: LoadOp::Clear(Color::NONE.into()),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                });
+                if image.texture_format != array.format
+                    || !image.texture.usage().contains(TextureUsages::COPY_SRC)
+                {
+                    continue;
+                }
+                let size = image.size.as_uvec2().min(array.size);
+                render_context.command_encoder().copy_texture_to_texture(
+                    image.texture.as_image_copy(),
+                    ImageCopyTexture {
+                        texture: &array.texture,
+                        mip_level: 0,
+                        origin: Origin3d {
+                            x: 0,
+                            y: 0,
+                            z: layer as u32,
+                        },
+                        aspect: TextureAspect::All,
+                    },
+                    Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+
+        // The mask of the transition, the fallback image when there is none or it isn't ready.
+        let transition_mask = transition
+            .and_then(|transition| transition.mask.as_ref())
+            .and_then(|mask| gpu_images.get(mask))
+            .unwrap_or(&post_process_pipeline.fallback_image);
+
+        // The samplers belong to the pipeline rather than to a dimension image,
+        // so they are available whatever the number of bound images.
+        let sampler = match settings.scaling_mode {
+            ScalingMode::Linear => &post_process_pipeline.linear_sampler,
+            ScalingMode::Nearest => &post_process_pipeline.nearest_sampler,
+        };
+
+        let mut entries = vec![
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::Sampler(sampler),
+            },
+            BindGroupEntry {
+                binding: 3,
+                resource: dimensions_binding,
+            },
+            BindGroupEntry {
+                binding: 4,
+                resource: settings_binding,
+            },
+            BindGroupEntry {
+                binding: 6,
+                resource: BindingResource::TextureView(post_process_main.source),
+            },
+            BindGroupEntry {
+                binding: 7,
+                resource: BindingResource::TextureView(&transition_mask.texture_view),
+            },
+        ];
+        if let Some(globals_binding) = globals_binding {
+            entries.push(BindGroupEntry {
+                binding: 0,
+                resource: globals_binding,
+            });
+        }
+        match texture_array {
+            Some(array) => entries.push(BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureView(&array.view),
+            }),
+            None if post_process_pipeline.binding_arrays => entries.push(BindGroupEntry {
+                binding: 1,
+                resource: BindingResource::TextureViewArray(&textures[..]),
+            }),
+            None => entries.extend(textures.iter().enumerate().map(|(slot, &texture)| {
+                BindGroupEntry {
+                    binding: INDIVIDUAL_TEXTURE_BINDING + slot as u32,
+                    resource: BindingResource::TextureView(texture),
+                }
+            })),
+        }
+        if post_process_pipeline.binding_arrays {
+            entries.push(BindGroupEntry {
+                binding: 5,
+                resource: BindingResource::TextureViewArray(&depth_textures[..]),
+            });
+        } else {
+            entries.extend(depth_textures.iter().enumerate().map(|(slot, &texture)| {
+                BindGroupEntry {
+                    binding: INDIVIDUAL_DEPTH_TEXTURE_BINDING + slot as u32,
+                    resource: BindingResource::TextureView(texture),
+                }
+            }));
+        }
+
         // The bind_group gets created each frame.
         //
         // Normally, you would create a bind_group in the Queue set, but this doesn't work with the post_process_write().
@@ -206,21 +827,34 @@ impl Node for PostProcessNode {
                 label: Some("post_process_bind_group"),
                 layout: &post_process_pipeline.layout,
                 // It's important for this to match the BindGroupLayout defined in the PostProcessPipeline
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: globals_binding,
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: BindingResource::TextureViewArray(&textures[..]),
-                    },
-                    BindGroupEntry {
-                        binding: 2,
-                        resource: BindingResource::Sampler(&images[0].sampler),
-                    },
-                ],
+                entries: &entries,
+            });
+
+        // Copy the main pass to the destination, for the composite to blend onto it.
+        // The whole texture is copied, so the main pass is kept outside of the camera viewport.
+        // Measured by the `PostProcessDiagnosticsPlugin`, when the GPU supports timestamps.
+        let timestamps = world
+            .get_resource::<PassTimestamps>()
+            .filter(|timestamps| timestamps.begin(render_context));
+
+        if let Some(copy_pipeline) = copy_pipeline {
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("post_process_copy_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: post_process_main.destination,
+                    resolve_target: None,
+                    ops: Operations::default(),
+                })],
+                depth_stencil_attachment: None,
             });
+            render_pass.set_render_pipeline(copy_pipeline);
+            render_pass.set_bind_group(
+                0,
+                &bind_group,
+                &[dimensions_uniform_index.index(), settings_index.index()],
+            );
+            render_pass.draw(0..3, 0..1);
+        }
 
         // Begin the render pass
         let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
@@ -228,110 +862,1683 @@ impl Node for PostProcessNode {
             color_attachments: &[Some(RenderPassColorAttachment {
                 // We need to specify the post process destination view here
                 // to make sure we write to the appropriate texture.
-                view: post_process_main.destination,
+                view: static_composite
+                    .map_or(post_process_main.destination, |composite| &composite.view),
                 resolve_target: None,
-                ops: Operations::default(),
+                // Keep the copied main pass when blending onto it.
+                ops: match (settings.composite_load, copy_pipeline) {
+                    (CompositeLoad::Clear(color), _) => Operations {
+                        load: LoadOp::Clear(color.into()),
+                        store: true,
+                    },
+                    (CompositeLoad::Load, Some(_)) => Operations {
+                        load: LoadOp::Load,
+                        store: true,
+                    },
+                    (CompositeLoad::Load, None) => Operations::default(),
+                },
             })],
             depth_stencil_attachment: None,
         });
 
+        // Confine the effect to the camera viewport (for split-screen),
+        // the fullscreen triangle then covers the viewport only.
+        if let Some(viewport) = viewport {
+            render_pass.set_camera_viewport(viewport);
+        }
+
         // This is mostly just wgpu boilerplate for drawing a fullscreen triangle,
         // using the pipeline/bind_group created above
         render_pass.set_render_pipeline(pipeline);
-        render_pass.set_bind_group(0, &bind_group, &[]);
+        // Dynamic offsets are given in the order of their bindings.
+        render_pass.set_bind_group(
+            0,
+            &bind_group,
+            &[dimensions_uniform_index.index(), settings_index.index()],
+        );
         render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+        if let Some(static_composite) = static_composite {
+            let destination = post_process_main.destination;
+            static_composite.copy_to(world, render_context, destination, viewport);
+        }
+        // `PostProcessSettings::feedback` blends the previous frames over the composite.
+        if let Some(history) = feedback_history(world, view_entity) {
+            history.apply(world, render_context, view_target_main, settings.feedback, viewport);
+        }
+        if let Some(timestamps) = timestamps {
+            timestamps.end(render_context);
+        }
+
+        // The view target textures can't be copied from, so the composite is drawn
+        // a second time into the capture target, at the capture target's own size.
+        // Unsuitable capture targets are skipped, they are reported by `check_capture_targets`.
+        let capture_target = self
+            .query_capture
+            .get_manual(world, view_entity)
+            .ok()
+            .and_then(|capture| gpu_images.get(&capture.0))
+            .filter(|capture| {
+                capture.texture_format == TextureFormat::bevy_default()
+                    && capture
+                        .texture
+                        .usage()
+                        .contains(TextureUsages::RENDER_ATTACHMENT)
+            });
+        // Capture targets are never HDR, they use the LDR pipeline whatever the view.
+        // It blends like the composite, the target is cleared first so captures don't pile up.
+        let capture_pipeline = capture_target
+            .and_then(|_| pipeline_cache.get_render_pipeline(post_process_pipeline.pipeline_id));
+        if let Some((capture_target, capture_pipeline)) = capture_target.zip(capture_pipeline) {
+            let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+                label: Some("post_process_capture_pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &capture_target.texture_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::NONE.into()),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_render_pipeline(capture_pipeline);
+            render_pass.set_bind_group(
+                0,
+                &bind_group,
+                &[dimensions_uniform_index.index(), settings_index.index()],
+            );
+            render_pass.draw(0..3, 0..1);
+        }
 
         Ok(())
     }
 }
 
+/// Marks the cameras the post process runs on.
+///
+/// Other cameras in the graph, such as a UI camera, are left untouched.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct PostProcessCamera;
+
+/// Also renders the final composited image of a [`PostProcessCamera`] into the given image,
+/// to be displayed as a mirror or a minimap for example.
+///
+/// Only the composite is captured, over a transparent background: the main pass isn't,
+/// whatever the [`CompositeBlend`].
+///
+/// The image must be a render target using [`TextureFormat::bevy_default`],
+/// otherwise a warning is logged and nothing is captured.
+#[derive(Component, Clone, Debug, Default, ExtractComponent, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct CaptureTarget(pub Handle<Image>);
+
+/// Warns about [`CaptureTarget`]s which can't be rendered to.
+fn check_capture_targets(
+    images: Res<Assets<Image>>,
+    captures: Query<&CaptureTarget, Changed<CaptureTarget>>,
+) {
+    for capture in captures.iter() {
+        let Some(image) = images.get(&capture.0) else {
+            continue;
+        };
+        let descriptor = &image.texture_descriptor;
+        if descriptor.format != TextureFormat::bevy_default() {
+            warn!(
+                "CaptureTarget image has format {:?} but the post process renders to {:?}, it will not be captured",
+                descriptor.format,
+                TextureFormat::bevy_default()
+            );
+        } else if !descriptor.usage.contains(TextureUsages::RENDER_ATTACHMENT) {
+            warn!("CaptureTarget image lacks the RENDER_ATTACHMENT usage, it will not be captured");
+        }
+    }
+}
+
+/// Warns about dimension images which can't be sampled by the post process.
+///
+/// Foreground dimensions are reported with their index in the `ForegroundDimensions`.
+fn check_dimension_images(
+    images: Res<Assets<Image>>,
+    dim: Query<
+        (&Dimensions, Option<&ForegroundDimensions>),
+        Or<(Changed<Dimensions>, Changed<ForegroundDimensions>)>,
+    >,
+) {
+    for (dimensions, foreground) in dim.iter() {
+        let groups = [
+            ("Dimension", Some(dimensions)),
+            ("Foreground dimension", foreground.map(|f| &f.0)),
+        ];
+        for (group, dimensions) in groups {
+            let Some(dimensions) = dimensions else {
+                continue;
+            };
+            for (index, dimension) in dimensions.dimensions.iter().enumerate() {
+                let Some(image) = images.get(&dimension.image) else {
+                    continue;
+                };
+                if image.texture_descriptor.sample_count != 1 {
+                    warn!(
+                        "{group} {index} has a multisampled image ({} samples), it can't be sampled by the post process. \
+                        Render dimension cameras to single sampled images: `Msaa` resolves into them.",
+                        image.texture_descriptor.sample_count
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Warns about dimension images which are not render targets, e.g. a sprite image put in a
+/// [`DimensionDef`](crate::DimensionDef) in place of the image of its camera: it would never update.
+/// The [`DimensionSource::External`] images are updated without a camera, they are not checked.
+#[cfg(debug_assertions)]
+fn check_dimension_render_targets(
+    images: Res<Assets<Image>>,
+    dim: Query<(&Dimensions, Option<&ForegroundDimensions>), Changed<Dimensions>>,
+) {
+    for (dimensions, foreground) in dim.iter() {
+        let foreground = foreground.into_iter().flat_map(|f| f.dimensions.iter());
+        for (index, dimension) in dimensions.dimensions.iter().chain(foreground).enumerate() {
+            if dimension.source == DimensionSource::External {
+                continue;
+            }
+            let Some(image) = images.get(&dimension.image) else {
+                continue;
+            };
+            if !image.texture_descriptor.usage.contains(TextureUsages::RENDER_ATTACHMENT) {
+                warn!(
+                    "Dimension {index} has an image without the RENDER_ATTACHMENT usage, no camera can render to it. \
+                    Use the image of its camera, see `create_render_target`."
+                );
+            }
+        }
+    }
+}
+
+/// Warns about dimension cameras which don't render before the camera compositing them,
+/// the post process would then show their previous frame.
+fn check_camera_order(
+    compositors: Query<
+        (Entity, &Camera, &Dimensions, Option<&ForegroundDimensions>),
+        Or<(Changed<Dimensions>, Changed<Camera>)>,
+    >,
+    cameras: Query<(Entity, &Camera)>,
+) {
+    for (entity, camera, dimensions, foreground) in compositors.iter() {
+        let foreground = foreground.into_iter().flat_map(|f| f.dimensions.iter());
+        for dimension in dimensions.dimensions.iter().chain(foreground) {
+            for (dimension_camera, _) in cameras.iter().filter(|(_, other)| {
+                other.order >= camera.order
+                    && matches!(
+                        &other.target,
+                        RenderTarget::Image(image) if *image == dimension.image
+                    )
+            }) {
+                warn!(
+                    "Dimension camera {dimension_camera:?} has an order greater or equal to the order {} \
+                    of the camera {entity:?} compositing it, it should render before it: lower its order.",
+                    camera.order
+                );
+            }
+        }
+    }
+}
+
+/// Warns once per camera when it has more dimensions than the post process binds,
+/// the extra ones are never shown.
+fn check_dimension_count(
+    mut warned: Local<HashSet<Entity>>,
+    config: Res<PostProcessConfig>,
+    dim: Query<
+        (Entity, &Dimensions, Option<&ForegroundDimensions>),
+        Or<(Changed<Dimensions>, Changed<ForegroundDimensions>)>,
+    >,
+) {
+    // The main world doesn't know whether the device supports binding arrays.
+    let max = config.texture_count(true);
+    for (entity, dimensions, foreground) in dim.iter() {
+        let count = dimensions
+            .dimensions
+            .len()
+            .max(foreground.map_or(0, |foreground| foreground.dimensions.len()));
+        if count > max && warned.insert(entity) {
+            warn!(
+                "Camera {entity:?} has {count} dimensions in a group, but only the first {max} are bound: \
+                the others will never be shown. To bind more, raise `PostProcessPlugin::max_textures`, \
+                or `PostProcessConfig::max_textures` at runtime, up to {MAX_TEXTURE_COUNT}."
+            );
+        }
+    }
+}
+
+/// Settings of the post process effect, add it to a [`PostProcessCamera`] to configure its effect.
+///
+/// When missing, the default settings are used.
+#[derive(Component, Clone, Copy, Debug, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct PostProcessSettings {
+    /// Size of the pixelation grid, in screen pixels. `1.0` means no pixelation.
+    pub pixel_size: f32,
+    /// How dimension textures are filtered when their resolution differs from the screen.
+    pub scaling_mode: ScalingMode,
+    /// Composites the first two background dimensions by depth instead of by selection:
+    /// the nearest fragment of both is shown.
+    ///
+    /// The dimensions must be rendered by 3d cameras set up with `create_depth_camera`.
+    /// This costs a depth texture binding and a depth read per dimension and pixel,
+    /// and keeps the depth textures of those cameras bindable, which prevents some GPU optimizations.
+    pub depth_compositing: bool,
+    /// How much the screen corners are darkened, `0.0` disables the vignette.
+    pub vignette_strength: f32,
+    /// Distance from the screen center where the vignette starts,
+    /// `1.0` being the middle of the screen edges.
+    pub vignette_radius: f32,
+    /// How far apart the red, green and blue channels are sampled, growing toward the screen edges.
+    ///
+    /// It is the UV shift of the red and blue channels at the middle of the screen edges,
+    /// `0.01` is already noticeable. `0.0` disables the effect, which samples the dimensions three times.
+    pub aberration_strength: f32,
+    /// How much every other screen row is darkened, like the scanlines of a CRT screen.
+    /// The scanlines scroll with time, `0.0` disables them.
+    pub scanline_intensity: f32,
+    /// How much the screen is bent like a CRT screen, `0.0` keeps it flat.
+    ///
+    /// The corners move away by this fraction of the screen size, what ends up outside of the screen is black.
+    pub curvature: f32,
+    /// Maps the composited HDR colors to the displayable range, before the other screen effects.
+    ///
+    /// Useful when the dimensions are rendered by `hdr` cameras into `Rgba16Float` images,
+    /// while the view of the post process is not HDR itself.
+    pub tonemap: TonemapMode,
+    /// Shows the selected dimension, or every dimension at once for inspection.
+    pub layout: CompositeLayout,
+    /// Multiplies the composited color, `1.0` keeps it as is.
+    ///
+    /// The color grading is applied after tonemapping, in linear space: the dimension images are sRGB,
+    /// sampling them converts to linear, and the view target converts back when it is sRGB.
+    pub brightness: f32,
+    /// Scales the distance of the color to middle gray, `1.0` keeps it as is and `0.0` gives a flat gray.
+    pub contrast: f32,
+    /// Raises the color to the power of `1.0 / gamma`, above `1.0` brightens the dark tones, `1.0` keeps it as is.
+    pub gamma: f32,
+    /// The composite is discarded where its alpha is below this threshold, keeping the main pass as is.
+    ///
+    /// For overlay dimensions cleared to a transparent color: where nothing was drawn, the main scene shows
+    /// untouched, even with `CompositeBlend::None`. `0.0` disables it.
+    pub overlay_threshold: f32,
+    /// Amplitude of an ordered dithering of the final color, in 8 bit steps of its sRGB encoding, to hide
+    /// the banding of smooth gradients, transitions and dark scenes. `1.0` is usually enough, `0.0` disables it.
+    pub dither_strength: f32,
+    /// Speed of the animated effects driven by the GPU time, like the scrolling scanlines
+    /// and the default composite. `1.0` is real time, `0.0` pauses them.
+    pub time_scale: f32,
+    /// What the composite is drawn onto: the main pass by default, or a clear color replacing it.
+    pub composite_load: CompositeLoad,
+    /// The color space the transitions and the foreground are blended in.
+    pub blend_space: BlendSpace,
+    /// Keeps the composite in a texture, and copies it on the frames where nothing it depends on changed,
+    /// instead of compositing again. For mostly static scenes, with dimension cameras rendering on demand,
+    /// see [`RenderFrequency`]. The `PostProcessDiagnosticsPlugin` pass time shows the saving.
+    ///
+    /// A frame is static when no dimension camera rendered, no transition or flash runs, and neither the settings,
+    /// the dimensions nor the viewport changed. Animated effects, driven by `time_scale` with
+    /// `PostProcessPlugin::globals`, and dimensions with their own shader make every frame dirty.
+    ///
+    /// It only applies when the composite doesn't show the main pass: with `CompositeBlend::None` and
+    /// an `overlay_threshold` of `0.0`, or `CompositeLoad::Clear`. Otherwise every frame is composited.
+    pub skip_static_frames: bool,
+    /// How much of the previous frames is blended over the composite, for motion trails:
+    /// each frame shows `current * (1 - feedback) + previous * feedback`. `0.0` disables it,
+    /// values close to `1.0` leave long trails.
+    ///
+    /// The previous frame is kept in a texture of the size and format of the view target, two actually:
+    /// each frame reads one and writes the other. The blend is a pass of its own after the composite.
+    /// Every frame is dirty with `skip_static_frames`, as the trails fade over time.
+    pub feedback: f32,
+}
+
+impl Default for PostProcessSettings {
+    fn default() -> Self {
+        Self {
+            pixel_size: 1.0,
+            scaling_mode: ScalingMode::default(),
+            depth_compositing: false,
+            vignette_strength: 0.0,
+            vignette_radius: 0.5,
+            aberration_strength: 0.0,
+            scanline_intensity: 0.0,
+            curvature: 0.0,
+            tonemap: TonemapMode::default(),
+            layout: CompositeLayout::default(),
+            brightness: 1.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            overlay_threshold: 0.0,
+            dither_strength: 0.0,
+            time_scale: 1.0,
+            composite_load: CompositeLoad::default(),
+            blend_space: BlendSpace::default(),
+            skip_static_frames: false,
+            feedback: 0.0,
+        }
+    }
+}
+
+/// Selects the sampler used to sample the dimension textures.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum ScalingMode {
+    /// Smooth bilinear filtering.
+    #[default]
+    Linear,
+    /// Crisp nearest filtering, useful for pixel art.
+    Nearest,
+}
+
+/// How the dimensions are laid out on screen, see [`PostProcessSettings::layout`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum CompositeLayout {
+    /// The selected dimension covers the screen, with the others blended in.
+    #[default]
+    Selection,
+    /// Tiles the bound background dimensions in a square grid of `cols` columns and rows,
+    /// in the order of `Dimensions::dimensions`, to see all of them at once when authoring levels.
+    ///
+    /// Dimensions beyond the grid capacity are omitted, as well as the ones which are not bound,
+    /// see `PostProcessPlugin::max_textures`. The foreground dimensions and transitions are not shown.
+    Grid { cols: u32 },
+}
+
+/// What the composite pass draws onto, see [`PostProcessSettings::composite_load`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect, FromReflect)]
+pub enum CompositeLoad {
+    /// The composite is blended onto the main pass, following the [`CompositeBlend`] of the plugin.
+    #[default]
+    Load,
+    /// The main pass is replaced by this color, the composite is blended onto it.
+    /// The main pass isn't copied, whatever the [`CompositeBlend`].
+    Clear(Color),
+}
+
+/// The color space the dimensions are mixed in by the transitions and the foreground,
+/// see [`PostProcessSettings::blend_space`].
+///
+/// The dimension textures and the view target are sRGB: sampling decodes the colors to linear,
+/// writing to the view target encodes them back. The colors of the settings, like the
+/// `TransitionKind::FadeThrough` color, are `Color`s which are converted to linear first, whatever the space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum BlendSpace {
+    /// Physically correct: a 50% crossfade is the linear midpoint of the two colors,
+    /// which looks brighter than the midpoint of their sRGB values.
+    #[default]
+    Linear,
+    /// Like image editors: the colors are encoded to sRGB before mixing, and decoded after,
+    /// a 50% crossfade is the midpoint of their sRGB values.
+    Srgb,
+}
+
+impl BlendSpace {
+    /// The color of a crossfade from `from` to `to` at `progress`, from 0 to 1, mixed in this space
+    /// like the shader mixes the dimensions. E.g. to match the color of a transition in the UI.
+    pub fn mix(self, from: Color, to: Color, progress: f32) -> Color {
+        let lerp = |from: [f32; 4], to: [f32; 4]| -> [f32; 4] {
+            std::array::from_fn(|i| from[i] + (to[i] - from[i]) * progress)
+        };
+        match self {
+            BlendSpace::Linear => {
+                let [r, g, b, a] = lerp(from.as_linear_rgba_f32(), to.as_linear_rgba_f32());
+                Color::rgba_linear(r, g, b, a)
+            }
+            BlendSpace::Srgb => {
+                let [r, g, b, a] = lerp(from.as_rgba_f32(), to.as_rgba_f32());
+                Color::rgba(r, g, b, a)
+            }
+        }
+    }
+}
+
+/// The tonemapping operator applied to the composited dimensions, see [`PostProcessSettings::tonemap`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect, FromReflect)]
+pub enum TonemapMode {
+    /// The colors are written as is, values above 1.0 are clipped by LDR views.
+    #[default]
+    None,
+    /// Simple and cheap, it desaturates the highlights.
+    Reinhard,
+    /// A fit of the ACES filmic curve, with more contrast.
+    AcesFitted,
+}
+
+/// The per-view data used in the shader,
+/// built from the [`PostProcessSettings`] and [`Dimensions`] of a [`PostProcessCamera`] when extracting.
+#[derive(Component, Clone, Copy, ShaderType)]
+struct PostProcessUniform {
+    pixel_size: f32,
+    /// Index of the selected dimension in `Dimensions::dimensions`.
+    selected_index: u32,
+    /// Number of dimensions bound to the shader.
+    dimension_count: u32,
+    /// Index of the selected dimension in `ForegroundDimensions::dimensions`.
+    foreground_selected_index: u32,
+    /// Number of foreground dimensions bound to the shader.
+    foreground_dimension_count: u32,
+    /// `PostProcessSettings::depth_compositing`, as a boolean.
+    depth_compositing: u32,
+    vignette_strength: f32,
+    vignette_radius: f32,
+    aberration_strength: f32,
+    scanline_intensity: f32,
+    curvature: f32,
+    /// Number of `transition_sources` in use, 0 when no transition is running.
+    transition_source_count: u32,
+    transition_feather: f32,
+    /// Whether the transition has a mask, as a boolean.
+    transition_masked: u32,
+    /// Whether the transition fades through `transition_fade_color`, as a boolean.
+    transition_fade: u32,
+    /// The `TransitionKind::FadeThrough` color, in linear space.
+    transition_fade_color: Vec4,
+    /// The `FlashOnSwitch` color in linear space, its alpha is the intensity of the flash.
+    flash_color: Vec4,
+    /// The bound dimensions of `DimensionTransition::sources`, oldest first.
+    /// Right after a `Vec4`, as arrays of a uniform must be 16 bytes aligned.
+    transition_sources: [TransitionSource; MAX_TEXTURE_COUNT],
+    /// `PostProcessSettings::tonemap`: 0 for none, 1 for Reinhard, 2 for ACES.
+    tonemap: u32,
+    /// The columns of `CompositeLayout::Grid`, 0 for `CompositeLayout::Selection`.
+    grid_columns: u32,
+    brightness: f32,
+    contrast: f32,
+    gamma: f32,
+    overlay_threshold: f32,
+    dither_strength: f32,
+    time_scale: f32,
+    /// `PostProcessSettings::blend_space`: 0 for linear, 1 for sRGB.
+    blend_space: u32,
+}
+
+/// A dimension a `DimensionTransition` goes away from, as seen by the shader.
+///
+/// Array elements of a uniform must be 16 bytes aligned, hence the padding.
+#[derive(Clone, Copy, Default, ShaderType)]
+struct TransitionSource {
+    /// The texture slot of the dimension.
+    slot: u32,
+    /// Progress of the transition away from the dimension.
+    progress: f32,
+    padding_1: u32,
+    padding_2: u32,
+}
+
+/// The components of a [`PostProcessCamera`] it is extracted with, along with its dimensions.
+type PostProcessCameraQuery = (
+    &'static PostProcessCamera,
+    Option<&'static PostProcessSettings>,
+    Option<&'static ForegroundDimensions>,
+    Option<&'static DimensionTransition>,
+    Option<&'static DimensionViewOffset>,
+    Option<&'static PeekDimension>,
+    Option<&'static FlashOnSwitch>,
+);
+
+/// The render world version of a [`PostProcessCamera`] showing `dimensions`, with `texture_count`
+/// dimensions bound per group.
+fn extract_post_process_camera(
+    dimensions: &Dimensions,
+    (marker, settings, foreground, transition, offset, peek, flash): QueryItem<
+        '_,
+        PostProcessCameraQuery,
+    >,
+    texture_count: usize,
+) -> (PostProcessCamera, PostProcessSettings, PostProcessUniform) {
+    let foreground = foreground.map(|foreground| &foreground.0);
+    let settings = settings.copied().unwrap_or_default();
+    let peek = peek.filter(|peek| peek.active);
+    // A peek ignores the view offset, and interrupts the transition unless it crossfades.
+    let offset = match peek {
+        Some(_) => 0,
+        None => offset.map_or(0, |offset| offset.0),
+    };
+    let transition = transition.filter(|_| peek.map_or(true, |peek| peek.crossfade));
+    // The slots of the dimensions transitioned from, skipping those which are not bound.
+    let mut transition_sources = [TransitionSource::default(); MAX_TEXTURE_COUNT];
+    let mut transition_source_count = 0;
+    if let Some(transition) = transition {
+        let selected = view_selected(dimensions, offset, peek);
+        let count = bound_dimensions(dimensions, selected, texture_count).count() as u32;
+        let len = (dimensions.dimensions.len() as u32).max(1);
+        for (from, progress) in transition.sources() {
+            let from = (from + offset) % len;
+            if from >= count {
+                continue;
+            }
+            transition_sources[transition_source_count] = TransitionSource {
+                slot: (from + count - selected % count) % count,
+                progress,
+                ..default()
+            };
+            transition_source_count += 1;
+        }
+    }
+    let fade_color = transition.and_then(|transition| match transition.kind {
+        TransitionKind::Crossfade => None,
+        TransitionKind::FadeThrough { color } => Some(color),
+    });
+    (
+        *marker,
+        settings,
+        PostProcessUniform {
+            pixel_size: settings.pixel_size,
+            selected_index: view_selected(dimensions, offset, peek),
+            dimension_count: bound_dimensions(
+                dimensions,
+                view_selected(dimensions, offset, peek),
+                texture_count,
+            )
+            .count() as u32,
+            foreground_selected_index: foreground.map_or(0, |foreground| foreground.selected),
+            foreground_dimension_count: foreground.map_or(0, |foreground| {
+                bound_dimensions(foreground, foreground.selected, texture_count).count() as u32
+            }),
+            depth_compositing: settings.depth_compositing as u32,
+            vignette_strength: settings.vignette_strength,
+            vignette_radius: settings.vignette_radius,
+            aberration_strength: settings.aberration_strength,
+            scanline_intensity: settings.scanline_intensity,
+            curvature: settings.curvature,
+            transition_source_count: transition_source_count as u32,
+            transition_feather: transition.map_or(0.0, |transition| transition.feather),
+            transition_masked: transition.map_or(0, |transition| transition.mask.is_some() as u32),
+            transition_fade: fade_color.is_some() as u32,
+            transition_fade_color: fade_color
+                .map_or(Vec4::ZERO, |color| color.as_linear_rgba_f32().into()),
+            transition_sources,
+            flash_color: flash.map_or(Vec4::ZERO, |flash| {
+                let [r, g, b, a] = flash.color.as_linear_rgba_f32();
+                Vec4::new(r, g, b, a * flash.intensity())
+            }),
+            tonemap: match settings.tonemap {
+                TonemapMode::None => 0,
+                TonemapMode::Reinhard => 1,
+                TonemapMode::AcesFitted => 2,
+            },
+            grid_columns: match settings.layout {
+                CompositeLayout::Selection => 0,
+                CompositeLayout::Grid { cols } => cols,
+            },
+            brightness: settings.brightness,
+            contrast: settings.contrast,
+            gamma: settings.gamma,
+            overlay_threshold: settings.overlay_threshold,
+            dither_strength: settings.dither_strength,
+            time_scale: settings.time_scale,
+            blend_space: match settings.blend_space {
+                BlendSpace::Linear => 0,
+                BlendSpace::Srgb => 1,
+            },
+        },
+    )
+}
+
+/// Dimensions provided by the render world, for the [`PostProcessCamera`]s without a [`Dimensions`] component.
+///
+/// Insert it in the render app and update it from render world systems, for texture sets computed there,
+/// like procedurally generated images. Its images must be in the `RenderAssets<Image>`.
+/// A `Dimensions` component on the camera takes precedence over it. The other components of the camera,
+/// like its `ForegroundDimensions` or `DimensionTransition`, still apply.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RenderWorldDimensions(pub Dimensions);
+
+/// Extracts the [`PostProcessCamera`]s along with their `Dimensions`, and the ones without `Dimensions`
+/// along with the [`RenderWorldDimensions`], as if they were their `Dimensions`.
+///
+/// The dimensions are bound following the [`PostProcessConfig`] of this frame,
+/// which the [`PostProcessPipeline`] is rebuilt for before rendering, see `prepare_post_process_pipeline`.
+fn extract_post_process_cameras(
+    mut commands: Commands,
+    mut previous_len: Local<usize>,
+    config: Extract<Res<PostProcessConfig>>,
+    render_device: Res<RenderDevice>,
+    render_world_dimensions: Option<Res<RenderWorldDimensions>>,
+    cameras: Extract<Query<(Entity, Option<&Dimensions>, PostProcessCameraQuery)>>,
+) {
+    let texture_count = config.texture_count(binding_arrays(&render_device));
+    let render_world_dimensions = render_world_dimensions
+        .as_ref()
+        .map(|dimensions| &dimensions.0);
+    let mut values = Vec::with_capacity(*previous_len);
+    for (entity, dimensions, camera) in cameras.iter() {
+        let Some(dimensions) = dimensions.or(render_world_dimensions) else {
+            continue;
+        };
+        let (_, _, foreground, _, offset, peek, _) = camera;
+        let extracted = extract_dimensions(dimensions, foreground, offset, peek, texture_count);
+        let camera = extract_post_process_camera(dimensions, camera, texture_count);
+        values.push((entity, (camera, extracted)));
+    }
+    *previous_len = values.len();
+    commands.insert_or_spawn_batch(values);
+}
+
+/// Whether the post process runs, `true` by default.
+///
+/// When `false`, the main pass of every view is shown untouched, for A/B comparisons or accessibility options.
+/// The dimension cameras keep rendering, see [`RenderFrequency`] or [`FreezeDimensions`] to stop them as well.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PostProcessEnabled(pub bool);
+
+impl Default for PostProcessEnabled {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// How the post process samples, binds and blends the dimensions, initialized from the [`PostProcessPlugin`] fields.
+///
+/// Changing it rebuilds the post process pipelines on the next frame, the ones of the previous configuration
+/// are dropped. Their shaders stay compiled in the `PipelineCache`, which can't forget them:
+/// change it on user request, e.g. from a settings menu, not every frame.
+#[derive(Resource, ExtractResource, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PostProcessConfig {
+    /// See [`PostProcessPlugin::address_mode_u`].
+    pub address_mode_u: AddressMode,
+    /// See [`PostProcessPlugin::address_mode_v`].
+    pub address_mode_v: AddressMode,
+    /// See [`PostProcessPlugin::anisotropy`].
+    pub anisotropy: u16,
+    /// See [`PostProcessPlugin::max_textures`].
+    pub max_textures: usize,
+    /// See [`PostProcessPlugin::storage`].
+    pub storage: DimensionStorage,
+    /// See [`PostProcessPlugin::blend`].
+    pub blend: CompositeBlend,
+    /// See [`PostProcessPlugin::globals`].
+    pub globals: bool,
+}
+
+impl PostProcessConfig {
+    /// The number of dimensions bound per group, see [`PostProcessPlugin::max_textures`].
+    fn texture_count(&self, binding_arrays: bool) -> usize {
+        if !binding_arrays {
+            return INDIVIDUAL_TEXTURE_COUNT;
+        }
+        // The default `composite_background` composites the first two slots.
+        self.max_textures.clamp(2, MAX_TEXTURE_COUNT)
+    }
+}
+
+/// The post process shader and its fragment entry point, see [`PostProcessPlugin::shader`].
+#[derive(Resource, Clone)]
+struct PostProcessShader {
+    shader: Handle<Shader>,
+    /// The asset path of the shader, `None` for the embedded one.
+    path: Option<&'static str>,
+    fragment_entry_point: &'static str,
+}
+
+/// Reports once why the post process pipeline can't be built, the post process doesn't run without it.
+///
+/// A shader which compiles again, e.g. fixed while hot reloading, is reported again on its next error.
+fn check_post_process_pipeline(
+    mut reported: Local<bool>,
+    pipeline: Res<PostProcessPipeline>,
+    shader: Res<PostProcessShader>,
+    pipeline_cache: Res<PipelineCache>,
+) {
+    match pipeline_cache.get_render_pipeline_state(pipeline.pipeline_id) {
+        CachedPipelineState::Err(err) if !*reported => {
+            error!(
+                "PostProcessPlugin: the post process pipeline can't be built from {}, the post process \
+                doesn't run until it compiles. Check that the shader defines the `{}` fragment entry point \
+                and the post process bindings: {err}",
+                shader.path.unwrap_or("the embedded `post_processing.wgsl`"),
+                shader.fragment_entry_point
+            );
+            *reported = true;
+        }
+        CachedPipelineState::Ok(_) => *reported = false,
+        _ => {}
+    }
+}
+
+/// Keeps the [`PostProcessPlugin::composite_shader`] loaded.
+#[derive(Resource)]
+struct CompositeShader(#[allow(dead_code)] Handle<Shader>);
+
+/// Rebuilds the [`PostProcessPipeline`] when the [`PostProcessConfig`] or the [`DimensionsFilterable`] change.
+///
+/// The previous pipeline is dropped, along with its layout, samplers and textures.
+fn prepare_post_process_pipeline(world: &mut World) {
+    let config = *world.resource::<PostProcessConfig>();
+    let filterable = world.resource::<DimensionsFilterable>().0;
+    let Some(pipeline) = world.get_resource::<PostProcessPipeline>() else {
+        return;
+    };
+    if pipeline.config == config && pipeline.filterable == filterable {
+        return;
+    }
+    let pipeline = PostProcessPipeline::from_world(world);
+    world.insert_resource(pipeline);
+}
+
+/// Whether all the bound dimension textures can be sampled with filtering, `false` as soon as one can't.
+///
+/// Formats like `Rgba32Float` are not filterable without `WgpuFeatures::FLOAT32_FILTERABLE`,
+/// binding them as filterable is a validation error: the pipelines are then rebuilt without filtering.
+#[derive(Resource)]
+struct DimensionsFilterable(bool);
+
+impl Default for DimensionsFilterable {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+fn prepare_dimensions_filterable(
+    mut filterable: ResMut<DimensionsFilterable>,
+    views: Query<&ExtractedDimensions>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    let features = render_device.features();
+    let all_filterable = views
+        .iter()
+        .flat_map(|dimensions| dimensions.slots.iter().flatten())
+        .filter_map(|dimension| gpu_images.get(&dimension.image))
+        .all(|image| {
+            image
+                .texture_format
+                .guaranteed_format_features(features)
+                .flags
+                .contains(TextureFormatFeatureFlags::FILTERABLE)
+        });
+    if filterable.0 != all_filterable {
+        filterable.0 = all_filterable;
+    }
+}
+
+/// Whether the camera rendering each dimension image had any visible entity this frame, for `DimensionDef::cull`.
+#[derive(Resource, Default)]
+struct DimensionContent(HashMap<Handle<Image>, bool>);
+
+impl DimensionContent {
+    /// Images which no camera renders to are assumed to have content.
+    fn has_content(&self, image: &Handle<Image>) -> bool {
+        self.0.get(image).copied().unwrap_or(true)
+    }
+}
+
+fn prepare_dimension_content(
+    mut content: ResMut<DimensionContent>,
+    cameras: Query<(&ExtractedCamera, &VisibleEntities)>,
+) {
+    content.0.clear();
+    for (camera, visible_entities) in cameras.iter() {
+        if let Some(NormalizedRenderTarget::Image(image)) = &camera.target {
+            // Several cameras may render to the same image.
+            let has_content = !visible_entities.entities.is_empty();
+            *content.0.entry(image.clone_weak()).or_default() |= has_content;
+        }
+    }
+}
+
+/// The array textures of [`DimensionStorage::TextureArray`], per view.
+#[derive(Resource, Default)]
+struct DimensionTextureArrays(HashMap<Entity, DimensionTextureArray>);
+
+/// The layers of a [`DimensionTextureArray`] are indexed like the texture slots.
+struct DimensionTextureArray {
+    texture: Texture,
+    view: TextureView,
+    /// One view per layer, to clear them.
+    layer_views: Vec<TextureView>,
+    size: UVec2,
+    format: TextureFormat,
+}
+
+/// Creates the array textures of [`DimensionStorage::TextureArray`],
+/// and recreates them when the size or format of the selected dimension changes.
+fn prepare_dimension_texture_arrays(
+    config: Res<PostProcessConfig>,
+    mut arrays: ResMut<DimensionTextureArrays>,
+    views: Query<(Entity, &ExtractedDimensions), With<PostProcessCamera>>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+) {
+    if config.storage != DimensionStorage::TextureArray {
+        return;
+    }
+    arrays.0.retain(|entity, _| views.contains(*entity));
+    for (entity, dimensions) in views.iter() {
+        let (size, format) = dimensions.slots[0]
+            .as_ref()
+            .and_then(|dimension| gpu_images.get(&dimension.image))
+            .map_or((UVec2::ONE, TextureFormat::bevy_default()), |image| {
+                (image.size.as_uvec2(), image.texture_format)
+            });
+        // One layer per slot, their number follows `PostProcessConfig::max_textures`.
+        let layers = dimensions.slots.len() as u32;
+        if let Some(array) = arrays.0.get(&entity) {
+            if array.size == size
+                && array.format == format
+                && array.layer_views.len() == layers as usize
+            {
+                continue;
+            }
+        }
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("post_process_dimension_texture_array"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: layers,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::D2Array),
+            ..default()
+        });
+        let layer_views = (0..layers)
+            .map(|layer| {
+                texture.create_view(&TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..default()
+                })
+            })
+            .collect();
+        arrays.0.insert(
+            entity,
+            DimensionTextureArray {
+                texture,
+                view,
+                layer_views,
+                size,
+                format,
+            },
+        );
+    }
+}
+
 // This contains global data used by the render pipeline. This will be created once on startup.
 #[derive(Resource, Clone, Debug)]
 struct PostProcessPipeline {
     layout: BindGroupLayout,
+    /// Used for [`ScalingMode::Linear`].
+    ///
+    /// The samplers of the dimension images are ignored, every dimension is sampled by the same sampler.
+    linear_sampler: Sampler,
+    /// Used for [`ScalingMode::Nearest`].
+    nearest_sampler: Sampler,
+    /// A 1x1 transparent black image, bound in the empty texture slots.
+    fallback_image: GpuImage,
+    /// A magenta and black checkerboard, bound in place of the dimension images which are not ready yet,
+    /// so missing render targets stand out.
+    missing_image: GpuImage,
+    /// A 1x1 depth texture at the far plane, bound in place of the missing dimension depth textures.
+    fallback_depth_view: TextureView,
     pipeline_id: CachedRenderPipelineId,
+    /// Copies the main pass to the destination texture, before blending onto it.
+    copy_pipeline_id: CachedRenderPipelineId,
+    /// The pipelines for HDR views, whose main textures use [`ViewTarget::TEXTURE_FORMAT_HDR`].
+    hdr_pipeline_id: CachedRenderPipelineId,
+    hdr_copy_pipeline_id: CachedRenderPipelineId,
+    /// Whether the textures are bound in binding arrays, or individually.
+    binding_arrays: bool,
+    /// The configuration the pipelines are built for.
+    config: PostProcessConfig,
+    /// Whether the dimension textures are bound as filterable, see [`DimensionsFilterable`].
+    filterable: bool,
 }
 
-const MAX_TEXTURE_COUNT: usize = 2;
+/// Without binding array support, the binding of the texture of the first slot, the other slots follow.
+const INDIVIDUAL_TEXTURE_BINDING: u32 = 10;
+/// Without binding array support, the binding of the depth texture of the first slot, the other slots follow.
+const INDIVIDUAL_DEPTH_TEXTURE_BINDING: u32 = 20;
+
+/// The largest [`PostProcessPlugin::max_textures`]: the uniforms have room for this many dimensions per group.
+pub const MAX_TEXTURE_COUNT: usize = 4;
+
+/// Without binding array support, the number of dimensions bound per group:
+/// the shader declares one binding per slot, from `texture_0` to `texture_3`.
+const INDIVIDUAL_TEXTURE_COUNT: usize = 2;
+
+/// Number of dimension groups: the background `Dimensions` and the `ForegroundDimensions`.
+const DIMENSION_GROUP_COUNT: usize = 2;
+
+/// Size of the uniform arrays of the dimensions: each group has room for `MAX_TEXTURE_COUNT` slots.
+/// The slots in use are packed, the background group comes first, then the foreground group.
+const BOUND_TEXTURE_COUNT: usize = MAX_TEXTURE_COUNT * DIMENSION_GROUP_COUNT;
+
+/// Whether the device can bind arrays of textures, WebGL2 and some native backends can't.
+fn binding_arrays(render_device: &RenderDevice) -> bool {
+    render_device
+        .features()
+        .contains(WgpuFeatures::TEXTURE_BINDING_ARRAY)
+}
+
+/// The dimension shown by a view: the active peek if any, otherwise the selection shifted by `offset`.
+fn view_selected(dimensions: &Dimensions, offset: u32, peek: Option<&PeekDimension>) -> u32 {
+    match peek {
+        Some(_) => PeekDimension::displayed(peek, dimensions),
+        None => dimensions.view_selected(offset),
+    }
+}
+
+/// Returns the dimensions in the order their textures are bound to the shader:
+/// the `selected` dimension first, then the following ones, wrapping around.
+///
+/// `selected` is the one of the view, see [`view_selected`].
+/// Only the first `texture_count` dimensions are considered, see [`PostProcessPlugin::max_textures`].
+fn bound_dimensions(
+    dimensions: &Dimensions,
+    selected: u32,
+    texture_count: usize,
+) -> impl Iterator<Item = &DimensionDef> {
+    let count = dimensions.dimensions.len().min(texture_count);
+    dimensions.dimensions[..count]
+        .iter()
+        .cycle()
+        .skip(selected as usize)
+        .take(count)
+}
+
+/// The largest blur radius, in texels: the blur has a fixed number of samples
+/// which get too far apart past this radius.
+const MAX_BLUR_RADIUS: f32 = 4.0;
+
+/// One item per texture slot of a dimension group: the bound dimensions, then `None` for the unused slots.
+fn group_slots(
+    dimensions: &Dimensions,
+    selected: u32,
+    texture_count: usize,
+) -> impl Iterator<Item = Option<&DimensionDef>> {
+    bound_dimensions(dimensions, selected, texture_count)
+        .map(Some)
+        .chain(std::iter::repeat(None))
+        .take(texture_count)
+}
+
+/// For each texture slot, the slot its texture is bound to.
+///
+/// Dimensions sharing the same image, in either group, are bound once to the first slot using it,
+/// so aliasing dimensions don't bind the same texture several times.
+fn texture_slots(
+    dimensions: &Dimensions,
+    selected: u32,
+    foreground: &Dimensions,
+    texture_count: usize,
+) -> Vec<u32> {
+    let images: Vec<_> = group_slots(dimensions, selected, texture_count)
+        .chain(group_slots(foreground, foreground.selected, texture_count))
+        .map(|dimension| dimension.map(|dimension| &dimension.image))
+        .collect();
+    images
+        .iter()
+        .enumerate()
+        .map(|(slot, image)| {
+            image
+                .and_then(|image| images.iter().position(|other| *other == Some(image)))
+                .unwrap_or(slot) as u32
+        })
+        .collect()
+}
+
+/// The image bound to the texture `slot` of `dimensions`, among the dimension `image`s:
+/// - empty slots get the transparent `fallback` image,
+/// - dimensions sharing an image only bind it once, the slots aliasing it get the `fallback` image too,
+/// - culled dimensions without visible content get the `fallback` image, which saves a texture fetch,
+/// - images which are not ready yet (on startup or while reloading) or invalid, for which `image`
+///   returns `None`, are replaced by the checkerboard `missing` image, so the dimensions which are ready still show.
+fn slot_image<'a, T>(
+    dimensions: &ExtractedDimensions,
+    slot: usize,
+    has_content: impl Fn(&Handle<Image>) -> bool,
+    image: impl Fn(&Handle<Image>) -> Option<&'a T>,
+    fallback: &'a T,
+    missing: &'a T,
+) -> &'a T {
+    match &dimensions.slots[slot] {
+        Some(dimension) if dimension.cull && !has_content(&dimension.image) => fallback,
+        Some(dimension) if dimensions.texture_slots[slot] == slot as u32 => {
+            image(&dimension.image).unwrap_or(missing)
+        }
+        _ => fallback,
+    }
+}
+
+/// Per-dimension settings, as seen by the shader.
+///
+/// Array elements of a uniform must be 16 bytes aligned, hence the padding.
+#[derive(Clone, Copy, ShaderType)]
+struct DimensionParams {
+    uv_offset: Vec2,
+    uv_scale: Vec2,
+    alpha: f32,
+    desaturate: f32,
+    /// Index of the dimension texture in the texture array, see [`texture_slots`].
+    texture_index: u32,
+    /// `DimensionDef::blur_radius`, clamped to [`MAX_BLUR_RADIUS`].
+    blur_radius: f32,
+    uv_matrix: Mat3,
+    /// 1 when the dimension texture uses premultiplied alpha.
+    premultiplied: u32,
+    /// 1 when the dimension is flipped horizontally.
+    flip_x: u32,
+    /// 1 when the dimension is flipped vertically.
+    flip_y: u32,
+    /// The mip level the dimension is sampled at.
+    lod_bias: f32,
+}
+
+impl Default for DimensionParams {
+    fn default() -> Self {
+        (&DimensionDef::default()).into()
+    }
+}
+
+impl From<&DimensionDef> for DimensionParams {
+    fn from(dimension: &DimensionDef) -> Self {
+        Self {
+            uv_offset: dimension.uv_offset,
+            uv_scale: dimension.uv_scale,
+            alpha: dimension.alpha,
+            desaturate: dimension.desaturate,
+            // Set by the extraction, which knows the slot of the dimension.
+            texture_index: 0,
+            blur_radius: dimension.blur_radius.clamp(0.0, MAX_BLUR_RADIUS),
+            uv_matrix: dimension.uv_matrix,
+            premultiplied: dimension.premultiplied.into(),
+            flip_x: dimension.flip_x.into(),
+            flip_y: dimension.flip_y.into(),
+            lod_bias: dimension.lod_bias.max(0.0),
+        }
+    }
+}
+
+/// The settings of every bound dimension of both groups, indexed like the texture array.
+///
+/// A struct with a single array has the layout of the array: the shader binds it as `params`,
+/// an array of `DimensionParams`. It has room for [`MAX_TEXTURE_COUNT`] dimensions per group,
+/// the unused elements are left to their default.
+#[derive(Component, Clone, Copy, Default, ShaderType)]
+struct DimensionsUniform {
+    dimensions: [DimensionParams; BOUND_TEXTURE_COUNT],
+}
+
+/// The render world version of the `Dimensions` and `ForegroundDimensions` of a camera.
+///
+/// Only the bound dimensions are extracted, already in slot order,
+/// so the dimension lists are not cloned every frame whatever their length.
+#[derive(Component, Clone, Default)]
+pub(crate) struct ExtractedDimensions {
+    /// The dimension of each texture slot, `None` for the unused slots.
+    /// Each group has `PostProcessPlugin::max_textures` slots.
+    pub(crate) slots: Vec<Option<ExtractedDimension>>,
+    /// See [`texture_slots`].
+    texture_slots: Vec<u32>,
+}
+
+impl ExtractedDimensions {
+    /// Whether no dimension is bound, in either group.
+    fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+}
+
+/// What the node needs of a bound `DimensionDef`, its settings are in the [`DimensionsUniform`].
+#[derive(Clone)]
+pub(crate) struct ExtractedDimension {
+    pub(crate) image: Handle<Image>,
+    depth_camera: Option<Entity>,
+    pub(crate) shader: Option<Handle<Shader>>,
+    cull: bool,
+}
+
+/// Extracts `dimensions` along with the `foreground` ones of the same camera and their uniform representation,
+/// so `UniformComponentPlugin` can upload it for us. `texture_count` dimensions are bound per group.
+///
+/// A missing `ForegroundDimensions` is extracted as an empty one.
+/// The `DimensionViewOffset` of the camera shifts the order of the background textures.
+fn extract_dimensions(
+    dimensions: &Dimensions,
+    foreground: Option<&ForegroundDimensions>,
+    offset: Option<&DimensionViewOffset>,
+    peek: Option<&PeekDimension>,
+    texture_count: usize,
+) -> (ExtractedDimensions, DimensionsUniform) {
+    let no_foreground = Dimensions::default();
+    let foreground = foreground.map_or(&no_foreground, |foreground| &foreground.0);
+    let peek = peek.filter(|peek| peek.active);
+    let selected = view_selected(dimensions, offset.map_or(0, |offset| offset.0), peek);
+    let texture_slots = texture_slots(dimensions, selected, foreground, texture_count);
+    let mut extracted = ExtractedDimensions {
+        slots: vec![None; texture_slots.len()],
+        texture_slots,
+    };
+    let mut uniform = DimensionsUniform::default();
+    for (slot, dimension) in group_slots(dimensions, selected, texture_count)
+        .chain(group_slots(foreground, foreground.selected, texture_count))
+        .enumerate()
+    {
+        if let Some(dimension) = dimension {
+            uniform.dimensions[slot] = dimension.into();
+            // Weak handles, the main world keeps the images alive.
+            extracted.slots[slot] = Some(ExtractedDimension {
+                image: dimension.image.clone_weak(),
+                depth_camera: dimension.depth_camera,
+                shader: dimension.shader.as_ref().map(Handle::clone_weak),
+                cull: dimension.cull,
+            });
+        }
+        uniform.dimensions[slot].texture_index = extracted.texture_slots[slot];
+    }
+    (extracted, uniform)
+}
+
+/// Extracts `dimensions` and their `foreground` like the render world does every frame, all of their
+/// groups bound, for the `extract` benchmark which can't reach the private extraction otherwise.
+#[doc(hidden)]
+pub fn extract_dimensions_bench(
+    dimensions: &Dimensions,
+    foreground: Option<&ForegroundDimensions>,
+) -> impl Sized {
+    extract_dimensions(dimensions, foreground, None, None, MAX_TEXTURE_COUNT)
+}
 
 impl FromWorld for PostProcessPipeline {
     fn from_world(world: &mut World) -> Self {
         let render_device = world.resource::<RenderDevice>();
+        let config = *world.resource::<PostProcessConfig>();
+        let filterable = world
+            .get_resource::<DimensionsFilterable>()
+            .map_or(true, |filterable| filterable.0);
+
+        // WebGL2 and some native backends can't bind arrays of textures,
+        // the textures of each slot are then bound individually.
+        let binding_arrays = binding_arrays(render_device);
+        let texture_count = config.texture_count(binding_arrays);
+        let slot_count = (texture_count * DIMENSION_GROUP_COUNT) as u32;
 
         // We need to define the bind group layout used for our pipeline
+        let mut entries = vec![
+            // @group(0) @binding(2) var nearest_sampler: sampler;
+            BindGroupLayoutEntry {
+                binding: 2,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Sampler(if filterable {
+                    SamplerBindingType::Filtering
+                } else {
+                    SamplerBindingType::NonFiltering
+                }),
+                count: None,
+                // Note: as textures, multiple samplers can also be bound onto one binding slot.
+                // One may need to pay attention to the limit of sampler binding amount on some platforms.
+                // count: NonZeroU32::new(MAX_TEXTURE_COUNT as u32),
+            },
+            // @group(0) @binding(3) var<uniform> params: array<DimensionParams, 8>;
+            BindGroupLayoutEntry {
+                binding: 3,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(DimensionsUniform::min_size()),
+                },
+                count: None,
+            },
+            // @group(0) @binding(4) var<uniform> settings: PostProcessUniform;
+            BindGroupLayoutEntry {
+                binding: 4,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: Some(PostProcessUniform::min_size()),
+                },
+                count: None,
+            },
+            // @group(0) @binding(6) var source: texture_2d<f32>;
+            BindGroupLayoutEntry {
+                binding: 6,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            // @group(0) @binding(7) var transition_mask: texture_2d<f32>;
+            BindGroupLayoutEntry {
+                binding: 7,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: true },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+        ];
+        if config.globals {
+            // The globals struct
+            entries.push(BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: Some(GlobalsUniform::min_size()),
+                },
+                count: None,
+            });
+        }
+        // @group(0) @binding(1) var textures: binding_array<texture_2d<f32>>;
+        // The textures of a binding array don't need to share the same size,
+        // so dimensions can have different resolutions.
+        // With `DimensionStorage::TextureArray`, it is a single `texture_2d_array<f32>`,
+        // without binding arrays, one `texture_2d<f32>` per slot from `INDIVIDUAL_TEXTURE_BINDING`.
+        let texture_entry = |binding, view_dimension, count| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable },
+                view_dimension,
+                multisampled: false,
+            },
+            count,
+        };
+        match (config.storage, binding_arrays) {
+            (DimensionStorage::TextureArray, _) => {
+                entries.push(texture_entry(1, TextureViewDimension::D2Array, None));
+            }
+            (DimensionStorage::BindingArray, true) => entries.push(texture_entry(
+                1,
+                TextureViewDimension::D2,
+                NonZeroU32::new(slot_count),
+            )),
+            (DimensionStorage::BindingArray, false) => {
+                entries.extend((0..slot_count).map(|slot| {
+                    texture_entry(
+                        INDIVIDUAL_TEXTURE_BINDING + slot,
+                        TextureViewDimension::D2,
+                        None,
+                    )
+                }));
+            }
+        }
+        // @group(0) @binding(5) var depth_textures: binding_array<texture_depth_2d>;
+        // Without binding arrays, one `texture_depth_2d` per slot from `INDIVIDUAL_DEPTH_TEXTURE_BINDING`.
+        let depth_texture_entry = |binding, count| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::FRAGMENT,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Depth,
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count,
+        };
+        if binding_arrays {
+            entries.push(depth_texture_entry(5, NonZeroU32::new(slot_count)));
+        } else {
+            entries
+                .extend((0..slot_count).map(|slot| {
+                    depth_texture_entry(INDIVIDUAL_DEPTH_TEXTURE_BINDING + slot, None)
+                }));
+        }
         let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("post_process_bind_group_layout"),
-            entries: &[
-                // The globals struct
-                BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: ShaderStages::VERTEX_FRAGMENT,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: Some(GlobalsUniform::min_size()),
-                    },
-                    count: None,
-                },
-                // @group(0) @binding(1) var textures: binding_array<texture_2d<f32>>;
-                BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Texture {
-                        sample_type: TextureSampleType::Float { filterable: true },
-                        view_dimension: TextureViewDimension::D2,
-                        multisampled: false,
+            entries: &entries,
+        });
+
+        // Non-filterable textures can only be sampled without filtering, `ScalingMode::Linear` then samples
+        // the nearest texel too.
+        let linear_filter = if filterable {
+            FilterMode::Linear
+        } else {
+            FilterMode::Nearest
+        };
+        // Anisotropic filtering needs every filter to be linear.
+        let anisotropic = filterable
+            && world
+                .resource::<RenderAdapter>()
+                .get_downlevel_capabilities()
+                .flags
+                .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+        let linear_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("post_process_linear_sampler"),
+            address_mode_u: config.address_mode_u,
+            address_mode_v: config.address_mode_v,
+            mag_filter: linear_filter,
+            min_filter: linear_filter,
+            mipmap_filter: linear_filter,
+            anisotropy_clamp: if anisotropic {
+                config.anisotropy.clamp(1, 16)
+            } else {
+                1
+            },
+            ..default()
+        });
+        let nearest_sampler = render_device.create_sampler(&SamplerDescriptor {
+            label: Some("post_process_nearest_sampler"),
+            address_mode_u: config.address_mode_u,
+            address_mode_v: config.address_mode_v,
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            ..default()
+        });
+
+        let render_queue = world.resource::<RenderQueue>();
+        let create_image = |label, size: u32, data: &[u8]| {
+            let format = TextureFormat::Rgba8UnormSrgb;
+            let texture = render_device.create_texture_with_data(
+                render_queue,
+                &TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size,
+                        height: size,
+                        depth_or_array_layers: 1,
                     },
-                    count: NonZeroU32::new(MAX_TEXTURE_COUNT as u32),
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                    view_formats: &[],
                 },
-                // @group(0) @binding(2) var nearest_sampler: sampler;
-                BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: ShaderStages::FRAGMENT,
-                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
-                    count: None,
-                    // Note: as textures, multiple samplers can also be bound onto one binding slot.
-                    // One may need to pay attention to the limit of sampler binding amount on some platforms.
-                    // count: NonZeroU32::new(MAX_TEXTURE_COUNT as u32),
+                data,
+            );
+            GpuImage {
+                texture_view: texture.create_view(&TextureViewDescriptor::default()),
+                texture,
+                texture_format: format,
+                sampler: render_device.create_sampler(&SamplerDescriptor::default()),
+                size: Vec2::splat(size as f32),
+                mip_level_count: 1,
+            }
+        };
+        let fallback_image = create_image("post_process_fallback_texture", 1, &[0, 0, 0, 0]);
+        // 8x8 cells of 2x2 texels, so the linear sampler only blurs their edges.
+        let missing_data: Vec<u8> = (0..16 * 16)
+            .flat_map(|texel| {
+                let (x, y) = (texel % 16 / 2, texel / 16 / 2);
+                if (x + y) % 2 == 0 {
+                    [255, 0, 255, 255]
+                } else {
+                    [0, 0, 0, 255]
+                }
+            })
+            .collect();
+        let missing_image = create_image("post_process_missing_texture", 16, &missing_data);
+
+        // wgpu zero-initializes textures, which is the far plane with bevy's reversed depth.
+        let fallback_depth_view = render_device
+            .create_texture(&TextureDescriptor {
+                label: Some("post_process_fallback_depth_texture"),
+                size: Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
                 },
-            ],
-        });
-        // Get the shader handle
-        let shader = world
-            .resource::<AssetServer>()
-            .load("shaders/post_processing.wgsl");
-
-        let pipeline_id = world
-            .resource_mut::<PipelineCache>()
-            // This will add the pipeline to the cache and queue it's creation
-            .queue_render_pipeline(RenderPipelineDescriptor {
-                label: Some("post_process_pipeline".into()),
-                layout: vec![layout.clone()],
-                // This will setup a fullscreen triangle for the vertex state
-                vertex: fullscreen_shader_vertex_state(),
-                fragment: Some(FragmentState {
-                    shader,
-                    shader_defs: vec![],
-                    // Make sure this matches the entry point of your shader.
-                    // It can be anything as long as it matches here and in the shader.
-                    entry_point: "fragment".into(),
-                    targets: vec![Some(ColorTargetState {
-                        format: TextureFormat::bevy_default(),
-                        blend: None,
-                        write_mask: ColorWrites::ALL,
-                    })],
-                }),
-                // All of the following property are not important for this effect so just use the default values.
-                // This struct doesn't have the Default trai implemented because not all field can have a default value.
-                primitive: PrimitiveState::default(),
-                depth_stencil: None,
-                multisample: MultisampleState::default(),
-                push_constant_ranges: vec![],
-            });
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Depth32Float,
+                usage: TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            })
+            .create_view(&TextureViewDescriptor::default());
+
+        // The shader is loaded by `PostProcessPlugin::build`.
+        let post_process_shader = world.resource::<PostProcessShader>().clone();
+        let shader = post_process_shader.shader;
+        let mut shader_defs = vec![ShaderDefVal::UInt(
+            "TEXTURE_COUNT".into(),
+            texture_count as u32,
+        )];
+        if config.storage == DimensionStorage::TextureArray {
+            shader_defs.push("DIMENSION_TEXTURE_ARRAY".into());
+        }
+        if !binding_arrays {
+            shader_defs.push("INDIVIDUAL_TEXTURE_BINDINGS".into());
+        }
+        if config.globals {
+            shader_defs.push("GLOBALS".into());
+        }
+
+        let descriptor = RenderPipelineDescriptor {
+            label: Some("post_process_pipeline".into()),
+            layout: vec![layout.clone()],
+            // This will setup a fullscreen triangle for the vertex state
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader,
+                shader_defs,
+                // Make sure this matches the entry point of your shader.
+                // It can be anything as long as it matches here and in the shader.
+                entry_point: post_process_shader.fragment_entry_point.into(),
+                targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    // The output uses straight alpha, it is blended onto the main pass.
+                    blend: config.blend.blend_state(),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            // The post process reads and writes the view target main textures, which are always single sampled:
+            // when `Msaa` is enabled, the main pass is resolved into them before post processing.
+            // So the pipeline doesn't follow the `Msaa` setting.
+            //
+            // All of the following property are not important for this effect so just use the default values.
+            // This struct doesn't have the Default trai implemented because not all field can have a default value.
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+        };
+        // The copy of the main pass shares the layout and bind group of the composite.
+        let copy_descriptor = RenderPipelineDescriptor {
+            label: Some("post_process_copy_pipeline".into()),
+            fragment: descriptor.fragment.clone().map(|fragment| FragmentState {
+                shader: POST_PROCESSING_SHADER_HANDLE.typed(),
+                entry_point: "copy_source".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: TextureFormat::bevy_default(),
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+                ..fragment
+            }),
+            ..descriptor.clone()
+        };
+        // The same pipelines, rendering to the main textures of HDR views.
+        let hdr = |descriptor: &RenderPipelineDescriptor| {
+            let mut descriptor = descriptor.clone();
+            descriptor.label = descriptor.label.map(|label| format!("{label}_hdr").into());
+            for target in descriptor
+                .fragment
+                .iter_mut()
+                .flat_map(|fragment| fragment.targets.iter_mut().flatten())
+            {
+                target.format = ViewTarget::TEXTURE_FORMAT_HDR;
+            }
+            descriptor
+        };
+        let hdr_descriptor = hdr(&descriptor);
+        let hdr_copy_descriptor = hdr(&copy_descriptor);
+
+        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
+        // This will add the pipeline to the cache and queue it's creation
+        let pipeline_id = pipeline_cache.queue_render_pipeline(descriptor);
+        let copy_pipeline_id = pipeline_cache.queue_render_pipeline(copy_descriptor);
+        let hdr_pipeline_id = pipeline_cache.queue_render_pipeline(hdr_descriptor);
+        let hdr_copy_pipeline_id = pipeline_cache.queue_render_pipeline(hdr_copy_descriptor);
 
         Self {
             layout,
+            linear_sampler,
+            nearest_sampler,
+            fallback_image,
+            missing_image,
+            fallback_depth_view,
             pipeline_id,
+            copy_pipeline_id,
+            hdr_pipeline_id,
+            hdr_copy_pipeline_id,
+            binding_arrays,
+            config,
+            filterable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::HandleId;
+
+    use super::*;
+
+    fn image() -> Handle<Image> {
+        Handle::weak(HandleId::random::<Image>())
+    }
+
+    fn extract(dimensions: &Dimensions) -> ExtractedDimensions {
+        extract_dimensions(dimensions, None, None, None, 2).0
+    }
+
+    #[test]
+    fn images_not_ready_get_the_missing_image() {
+        let (ready, loading) = (image(), image());
+        let dimensions = extract(&Dimensions::from_handles([ready.clone(), loading]));
+        // On startup, the `GpuImage` of a dimension can be prepared frames after the others.
+        let slots: Vec<_> = (0..dimensions.slots.len())
+            .map(|slot| {
+                slot_image(
+                    &dimensions,
+                    slot,
+                    |_| true,
+                    |image| (*image == ready).then_some(&"ready"),
+                    &"fallback",
+                    &"missing",
+                )
+            })
+            .collect();
+        assert_eq!(slots, [&"ready", &"missing", &"fallback", &"fallback"]);
+    }
+
+    #[test]
+    fn empty_dimensions_are_skipped() {
+        assert!(extract(&Dimensions::default()).is_empty());
+
+        let foreground = ForegroundDimensions(Dimensions::from_handles([image()]));
+        let background = Dimensions::default();
+        let extracted = extract_dimensions(&background, Some(&foreground), None, None, 2).0;
+        assert!(!extracted.is_empty());
+        let background_slots = &extracted.slots[..2];
+        assert!(background_slots.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn slots_follow_max_textures() {
+        let dimensions = Dimensions::from_handles([image(), image(), image()]);
+        let foreground = ForegroundDimensions(Dimensions::from_handles([image()]));
+        for max_textures in [2, 4, 3] {
+            let config = PostProcessConfig {
+                max_textures,
+                ..PostProcessPlugin::default().config()
+            };
+            let texture_count = config.texture_count(true);
+            assert_eq!(texture_count, max_textures);
+            let (extracted, uniform) =
+                extract_dimensions(&dimensions, Some(&foreground), None, None, texture_count);
+            assert_eq!(extracted.slots.len(), 2 * texture_count);
+            let bound = texture_count.min(dimensions.dimensions.len());
+            let (background, unused) = extracted.slots[..texture_count].split_at(bound);
+            assert!(background.iter().all(Option::is_some));
+            assert!(unused.iter().all(Option::is_none));
+            // The foreground group starts right after the background one.
+            assert!(extracted.slots[texture_count].is_some());
+            let texture_index = uniform.dimensions[texture_count].texture_index;
+            assert_eq!(texture_index, texture_count as u32);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn max_textures_is_clamped() {
+        let config = |max_textures| PostProcessConfig {
+            max_textures,
+            ..PostProcessPlugin::default().config()
+        };
+        assert_eq!(config(0).texture_count(true), 2);
+        assert_eq!(config(100).texture_count(true), MAX_TEXTURE_COUNT);
+        assert_eq!(config(4).texture_count(false), INDIVIDUAL_TEXTURE_COUNT);
+    }
+
+    /// The number of dimension textures actually bound, the other slots get the fallback image.
+    fn bound_texture_count(dimensions: &ExtractedDimensions) -> usize {
+        (0..dimensions.slots.len())
+            .filter(|&slot| {
+                dimensions.slots[slot].is_some() && dimensions.texture_slots[slot] == slot as u32
+            })
+            .count()
+    }
+
+    #[test]
+    fn removed_and_aliasing_dimensions_are_not_bound() {
+        let (first, second) = (image(), image());
+        let mut dimensions = Dimensions::from_handles([first.clone(), second]);
+        assert_eq!(bound_texture_count(&extract(&dimensions)), 2);
+
+        dimensions.dimensions.pop();
+        assert_eq!(bound_texture_count(&extract(&dimensions)), 1);
+
+        // A dimension sharing the image of another one binds it once.
+        dimensions = dimensions.add_dimension(DimensionDef {
+            image: first,
+            ..default()
+        });
+        let extracted = extract(&dimensions);
+        assert_eq!(extracted.texture_slots[1], 0);
+        assert_eq!(bound_texture_count(&extracted), 1);
+    }
+
+    #[test]
+    fn crossfade_midpoint() {
+        let close = |actual: [f32; 4], expected: [f32; 4]| {
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| (actual - expected).abs() < 1e-3)
+        };
+        let black = Color::BLACK;
+        let white = Color::WHITE;
+        // Half of the light of white, which is encoded well above the middle of the sRGB range.
+        let linear = BlendSpace::Linear.mix(black, white, 0.5);
+        assert!(close(linear.as_linear_rgba_f32(), [0.5, 0.5, 0.5, 1.0]));
+        assert!(close(linear.as_rgba_f32(), [0.735, 0.735, 0.735, 1.0]));
+        // The middle of the sRGB range, which is darker than half of the light.
+        let srgb = BlendSpace::Srgb.mix(black, white, 0.5);
+        assert!(close(srgb.as_rgba_f32(), [0.5, 0.5, 0.5, 1.0]));
+        assert!(close(srgb.as_linear_rgba_f32(), [0.214, 0.214, 0.214, 1.0]));
+        // The ends are the colors themselves.
+        let red = Color::RED;
+        let blue = Color::BLUE;
+        let start = BlendSpace::Linear.mix(red, blue, 0.0);
+        assert!(close(start.as_rgba_f32(), red.as_rgba_f32()));
+        let end = BlendSpace::Srgb.mix(red, blue, 1.0);
+        assert!(close(end.as_rgba_f32(), blue.as_rgba_f32()));
+    }
+}
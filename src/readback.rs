@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::{
+    prelude::*,
+    render::{
+        render_asset::RenderAssets,
+        render_resource::{
+            BufferDescriptor, BufferUsages, CommandEncoderDescriptor, Extent3d, ImageCopyBuffer,
+            ImageDataLayout, Maintain, MapMode, TextureFormat, TextureUsages,
+            COPY_BYTES_PER_ROW_ALIGNMENT,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        Extract, Render, RenderApp, RenderSet,
+    },
+};
+
+/// Copies dimension textures back to the CPU, for save-game thumbnails for example.
+///
+/// Send a [`ReadbackRequest`] with the image of a [`DimensionDef`](crate::DimensionDef),
+/// a [`DimensionSnapshot`] with its pixels is sent once the GPU copy completes.
+/// This takes at least one frame, usually a few: the copy is submitted after the frame
+/// is rendered, and the pixels are available once the GPU is done with it.
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub struct DimensionReadbackPlugin;
+
+impl Plugin for DimensionReadbackPlugin {
+    fn build(&self, app: &mut App) {
+        let results = ReadbackResults::default();
+        app.add_event::<ReadbackRequest>()
+            .add_event::<DimensionSnapshot>()
+            .insert_resource(results.clone())
+            .add_systems(PreUpdate, send_dimension_snapshots);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .insert_resource(results)
+            .init_resource::<PendingReadbacks>()
+            .add_systems(ExtractSchedule, extract_readback_requests)
+            // After the frame is rendered, so the copy sees this frame's content.
+            .add_systems(Render, copy_readbacks.after(RenderSet::Render));
+    }
+}
+
+/// Asks for a [`DimensionSnapshot`] of `image`.
+///
+/// The image needs the `COPY_SRC` usage, which [`create_render_target`](crate::create_render_target) sets.
+/// Requests for images which are not on the GPU yet are retried on the next frames.
+#[derive(Debug, Clone)]
+pub struct ReadbackRequest {
+    pub image: Handle<Image>,
+}
+
+/// The pixels of an image, as requested by a [`ReadbackRequest`].
+#[derive(Debug, Clone)]
+pub struct DimensionSnapshot {
+    pub image: Handle<Image>,
+    pub size: UVec2,
+    pub format: TextureFormat,
+    /// The rows of pixels, tightly packed, in the image format.
+    pub data: Vec<u8>,
+}
+
+/// Snapshots whose copy completed, filled by the buffer mapping callbacks of the render world
+/// and drained by the main world.
+#[derive(Resource, Clone, Default)]
+struct ReadbackResults(Arc<Mutex<Vec<DimensionSnapshot>>>);
+
+/// The images to copy at the end of the next frame.
+#[derive(Resource, Default)]
+struct PendingReadbacks(Vec<Handle<Image>>);
+
+fn send_dimension_snapshots(
+    results: Res<ReadbackResults>,
+    mut snapshots: EventWriter<DimensionSnapshot>,
+) {
+    let mut results = results.0.lock().unwrap();
+    snapshots.send_batch(results.drain(..));
+}
+
+fn extract_readback_requests(
+    mut pending: ResMut<PendingReadbacks>,
+    mut requests: Extract<EventReader<ReadbackRequest>>,
+) {
+    pending
+        .0
+        .extend(requests.iter().map(|request| request.image.clone_weak()));
+}
+
+fn copy_readbacks(
+    mut pending: ResMut<PendingReadbacks>,
+    results: Res<ReadbackResults>,
+    gpu_images: Res<RenderAssets<Image>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    if pending.0.is_empty() {
+        return;
+    }
+    let mut encoder = render_device.create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("dimension_readback"),
+    });
+    let mut copies = vec![];
+    pending.0.retain(|image| {
+        let Some(gpu_image) = gpu_images.get(image) else {
+            // Not ready yet, try again next frame.
+            return true;
+        };
+        if !gpu_image.texture.usage().contains(TextureUsages::COPY_SRC) {
+            warn!("ReadbackRequest: image {image:?} doesn't have the COPY_SRC usage, it can't be read back");
+            return false;
+        }
+        let Some(pixel_size) = gpu_image.texture_format.block_size(None) else {
+            warn!("ReadbackRequest: image {image:?} has a format which can't be read back");
+            return false;
+        };
+        let size = gpu_image.size.as_uvec2();
+        // Buffer rows must be aligned, the padding is removed once mapped.
+        let bytes_per_row = size.x * pixel_size;
+        let padded_bytes_per_row = bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = render_device.create_buffer(&BufferDescriptor {
+            label: Some("dimension_readback_buffer"),
+            size: (padded_bytes_per_row * size.y) as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            gpu_image.texture.as_image_copy(),
+            ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                ..default()
+            },
+        );
+        copies.push((
+            DimensionSnapshot {
+                image: image.clone_weak(),
+                size,
+                format: gpu_image.texture_format,
+                data: vec![],
+            },
+            buffer,
+            bytes_per_row as usize,
+            padded_bytes_per_row as usize,
+        ));
+        false
+    });
+    render_queue.submit([encoder.finish()]);
+
+    for (mut snapshot, buffer, bytes_per_row, padded_bytes_per_row) in copies {
+        let results = results.clone();
+        let mapped_buffer = buffer.clone();
+        buffer.slice(..).map_async(MapMode::Read, move |result| {
+            if let Err(err) = result {
+                warn!("ReadbackRequest: failed to read back {:?}: {err}", snapshot.image);
+                return;
+            }
+            {
+                let mapped = mapped_buffer.slice(..).get_mapped_range();
+                snapshot.data = mapped
+                    .chunks(padded_bytes_per_row)
+                    .flat_map(|row| &row[..bytes_per_row])
+                    .copied()
+                    .collect();
+            }
+            mapped_buffer.unmap();
+            results.0.lock().unwrap().push(snapshot);
+        });
+    }
+    // The mapping callbacks run when the device is polled, once the copy is done.
+    render_device.wgpu_device().poll(Maintain::Poll);
+}
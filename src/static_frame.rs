@@ -0,0 +1,324 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+    prelude::*,
+    reflect::TypeUuid,
+    render::{
+        camera::{ExtractedCamera, RenderTarget, Viewport},
+        render_resource::{
+            BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+            BindGroupLayoutEntry, BindingResource, BindingType, CachedRenderPipelineId,
+            ColorTargetState, ColorWrites, Extent3d, FragmentState, MultisampleState, Operations,
+            PipelineCache, PrimitiveState, RenderPassColorAttachment, RenderPassDescriptor,
+            RenderPipelineDescriptor, ShaderStages, TextureDescriptor, TextureDimension,
+            TextureFormat, TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor,
+            TextureViewDimension,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::{ExtractedView, ViewTarget},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    utils::HashMap,
+};
+
+use crate::{
+    DimensionSource, DimensionTransition, DimensionViewOffset, Dimensions, FlashOnSwitch,
+    ForegroundDimensions, PeekDimension, PostProcessCamera, PostProcessConfig, PostProcessEnabled,
+    PostProcessSettings,
+};
+
+const STATIC_FRAME_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 4_529_106_783_346_917_851);
+
+/// Keeps the composite of the views with [`PostProcessSettings::skip_static_frames`],
+/// to copy it on static frames instead of compositing again.
+///
+/// It is added by [`PostProcessPlugin`](crate::PostProcessPlugin).
+pub(crate) struct StaticFramePlugin;
+
+impl Plugin for StaticFramePlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            STATIC_FRAME_SHADER_HANDLE,
+            "../assets/shaders/static_frame.wgsl",
+            Shader::from_wgsl
+        );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_systems(ExtractSchedule, extract_static_frames)
+            .add_systems(Render, prepare_static_composites.in_set(RenderSet::Prepare));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<StaticComposites>();
+    }
+}
+
+/// Whether the composite of a view with `skip_static_frames` must be drawn again this frame.
+#[derive(Component)]
+struct StaticFrame {
+    dirty: bool,
+}
+
+/// Extracts whether anything the composite depends on changed since the previous frame.
+///
+/// The change detection is relative to the previous extraction, so a change is seen on the next frame only.
+#[allow(clippy::type_complexity)]
+fn extract_static_frames(
+    mut commands: Commands,
+    config: Extract<Res<PostProcessConfig>>,
+    enabled: Extract<Res<PostProcessEnabled>>,
+    views: Extract<
+        Query<
+            (
+                Entity,
+                Ref<Camera>,
+                Ref<PostProcessSettings>,
+                Ref<Dimensions>,
+                Option<Ref<ForegroundDimensions>>,
+                Option<&DimensionTransition>,
+                Option<Ref<PeekDimension>>,
+                Option<Ref<DimensionViewOffset>>,
+                Option<&FlashOnSwitch>,
+            ),
+            With<PostProcessCamera>,
+        >,
+    >,
+    cameras: Extract<Query<&Camera>>,
+) {
+    for (entity, camera, settings, dimensions, foreground, transition, peek, offset, flash) in
+        views.iter()
+    {
+        if !settings.skip_static_frames {
+            continue;
+        }
+        let images: Vec<_> = dimensions
+            .dimensions
+            .iter()
+            .chain(foreground.iter().flat_map(|foreground| foreground.dimensions.iter()))
+            .collect();
+        // A dimension camera which rendered this frame changed its image.
+        let rendered = cameras.iter().any(|camera| match &camera.target {
+            RenderTarget::Image(image) => {
+                camera.is_active && images.iter().any(|dimension| dimension.image == *image)
+            }
+            _ => false,
+        });
+        let animated = (config.globals && settings.time_scale != 0.0) || settings.feedback > 0.0;
+        let shaded = images.iter().any(|dimension| dimension.shader.is_some());
+        // Nothing tells when an external image is updated.
+        let external = images
+            .iter()
+            .any(|dimension| dimension.source == DimensionSource::External);
+        let changed = enabled.is_changed()
+            || camera.is_changed()
+            || settings.is_changed()
+            || dimensions.is_changed()
+            || foreground.as_ref().is_some_and(|foreground| foreground.is_changed())
+            || peek.is_some_and(|peek| peek.is_changed())
+            || offset.is_some_and(|offset| offset.is_changed());
+        let transitioning = transition.is_some_and(|transition| transition.from().is_some());
+        let flashing = flash.is_some_and(|flash| flash.intensity() > 0.0);
+        commands.get_or_spawn(entity).insert(StaticFrame {
+            dirty: rendered
+                || animated
+                || shaded
+                || external
+                || changed
+                || transitioning
+                || flashing,
+        });
+    }
+}
+
+/// The kept composites, and the pipelines copying them to the view target, one per view target format.
+#[derive(Resource)]
+struct StaticComposites {
+    layout: BindGroupLayout,
+    pipelines: HashMap<TextureFormat, CachedRenderPipelineId>,
+    composites: HashMap<Entity, StaticComposite>,
+}
+
+/// The composite kept for a view.
+pub(crate) struct StaticComposite {
+    pub(crate) view: TextureView,
+    size: UVec2,
+    format: TextureFormat,
+    pipeline_id: CachedRenderPipelineId,
+    /// Whether it must be drawn again this frame, rather than copied.
+    pub(crate) dirty: bool,
+}
+
+impl FromWorld for StaticComposites {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("post_process_static_frame_bind_group_layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        Self {
+            layout,
+            pipelines: HashMap::default(),
+            composites: HashMap::default(),
+        }
+    }
+}
+
+/// Creates the textures the composites are kept in, at the size and format of their view target.
+///
+/// A new texture is empty, so its view is dirty. The textures of the views which don't skip
+/// static frames anymore are dropped.
+fn prepare_static_composites(
+    mut static_composites: ResMut<StaticComposites>,
+    views: Query<(Entity, &StaticFrame, &ExtractedView, &ExtractedCamera)>,
+    render_device: Res<RenderDevice>,
+    mut pipeline_cache: ResMut<PipelineCache>,
+) {
+    let static_composites = &mut *static_composites;
+    let mut used = Vec::new();
+    for (entity, static_frame, view, camera) in views.iter() {
+        let Some(size) = camera.physical_target_size else {
+            continue;
+        };
+        used.push(entity);
+        // The format of the view target main textures.
+        let format = if view.hdr {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        if let Some(composite) = static_composites.composites.get_mut(&entity) {
+            if composite.size == size && composite.format == format {
+                composite.dirty = static_frame.dirty;
+                continue;
+            }
+        }
+
+        let pipeline_id = *static_composites
+            .pipelines
+            .entry(format)
+            .or_insert_with(|| {
+                pipeline_cache.queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("post_process_static_frame_pipeline".into()),
+                    layout: vec![static_composites.layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader: STATIC_FRAME_SHADER_HANDLE.typed(),
+                        shader_defs: vec![],
+                        entry_point: "copy_composite".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format,
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                })
+            });
+        let texture = render_device.create_texture(&TextureDescriptor {
+            label: Some("post_process_static_composite"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        static_composites.composites.insert(
+            entity,
+            StaticComposite {
+                view: texture.create_view(&TextureViewDescriptor::default()),
+                size,
+                format,
+                pipeline_id,
+                dirty: true,
+            },
+        );
+    }
+    static_composites
+        .composites
+        .retain(|entity, _| used.contains(entity));
+}
+
+/// The composite kept for `view_entity`, `None` if it doesn't skip static frames
+/// or until the copy pipeline is ready: it is then composited as usual.
+pub(crate) fn static_composite(world: &World, view_entity: Entity) -> Option<&StaticComposite> {
+    let composite = world
+        .get_resource::<StaticComposites>()?
+        .composites
+        .get(&view_entity)?;
+    world
+        .resource::<PipelineCache>()
+        .get_render_pipeline(composite.pipeline_id)?;
+    Some(composite)
+}
+
+impl StaticComposite {
+    /// Copies the kept composite to `destination`, within the camera `viewport` if any.
+    pub(crate) fn copy_to(
+        &self,
+        world: &World,
+        render_context: &mut RenderContext,
+        destination: &TextureView,
+        viewport: Option<&Viewport>,
+    ) {
+        let static_composites = world.resource::<StaticComposites>();
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(self.pipeline_id)
+        else {
+            return;
+        };
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("post_process_static_frame_bind_group"),
+                layout: &static_composites.layout,
+                entries: &[BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&self.view),
+                }],
+            });
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_process_static_frame_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+        if let Some(viewport) = viewport {
+            render_pass.set_camera_viewport(viewport);
+        }
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,230 @@
+use bevy::{prelude::*, render::extract_component::ExtractComponent};
+
+use crate::{Dimensions, PeekDimension, MAX_TEXTURE_COUNT};
+
+/// Animates the switches of the selected dimension of the `Dimensions` on the same entity,
+/// instead of switching instantly.
+///
+/// The previous dimension crossfades into the new one, or fades through a color, see [`TransitionKind`].
+/// Either way, it can be wiped following `mask` instead of faded uniformly.
+/// Only the bound dimensions can be transitioned from, see `PostProcessPlugin::max_textures`:
+/// switching from another one is instant.
+///
+/// Switching again during a transition chains a new transition, the running ones keep going:
+/// up to [`MAX_TEXTURE_COUNT`] previous dimensions are blended at once, see [`DimensionTransition::sources`].
+#[derive(Component, Clone, Debug, ExtractComponent, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct DimensionTransition {
+    /// Seconds a transition lasts.
+    pub duration: f32,
+    /// A grayscale image shaping the transition: the new dimension is revealed where the mask is darker
+    /// than the progress, so black parts show the new dimension first and white parts last.
+    ///
+    /// Without a mask, the dimensions crossfade.
+    pub mask: Option<Handle<Image>>,
+    /// Width of the gradient at the edge of the wipe, in mask values. `0.0` gives a hard edge.
+    pub feather: f32,
+    /// How the previous dimension gives way to the new one.
+    pub kind: TransitionKind,
+    /// The running transitions, oldest first: the dimension each one goes away from, and its elapsed fraction.
+    /// Each one goes to the dimension of the next one, the last one to the displayed dimension.
+    sources: Vec<(u32, f32)>,
+    /// The selected dimension on the previous update, to detect switches.
+    last_selected: Option<u32>,
+}
+
+impl Default for DimensionTransition {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl DimensionTransition {
+    /// A crossfade lasting `duration` seconds.
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            mask: None,
+            feather: 0.1,
+            kind: TransitionKind::Crossfade,
+            sources: Vec::new(),
+            last_selected: None,
+        }
+    }
+
+    /// Fades the previous dimension out to `color`, then the new dimension in from it.
+    pub fn fade_through(mut self, color: Color) -> Self {
+        self.kind = TransitionKind::FadeThrough { color };
+        self
+    }
+
+    /// Shapes the transition with a grayscale mask, see [`DimensionTransition::mask`].
+    pub fn with_mask(mut self, mask: Handle<Image>) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// The dimension transitioned from by the latest transition, `None` when no transition is running.
+    pub fn from(&self) -> Option<u32> {
+        self.sources.last().map(|(from, _)| *from)
+    }
+
+    /// The progress of the latest transition, from 0 to 1, `None` when no transition is running.
+    pub fn progress(&self) -> Option<f32> {
+        self.sources.last().map(|(_, progress)| *progress)
+    }
+
+    /// The running transitions, oldest first: the dimension each one goes away from, and its progress.
+    ///
+    /// Each dimension is revealed over the previous ones following the progress of the transition before it,
+    /// the displayed dimension following the progress of the last one.
+    pub fn sources(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.sources.iter().copied()
+    }
+
+    /// The phase of the running transition, `None` when no transition is running.
+    ///
+    /// A [`TransitionKind::FadeThrough`] spends the first half of its duration in [`TransitionPhase::Out`],
+    /// and the second half in [`TransitionPhase::In`]. A crossfade is a single [`TransitionPhase::In`].
+    pub fn phase(&self) -> Option<TransitionPhase> {
+        let progress = self.progress()?;
+        Some(match self.kind {
+            TransitionKind::Crossfade => TransitionPhase::In(progress),
+            TransitionKind::FadeThrough { .. } if progress < 0.5 => {
+                TransitionPhase::Out(progress * 2.0)
+            }
+            TransitionKind::FadeThrough { .. } => TransitionPhase::In(progress * 2.0 - 1.0),
+        })
+    }
+}
+
+/// How a [`DimensionTransition`] goes from the previous dimension to the new one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect, FromReflect)]
+pub enum TransitionKind {
+    /// The previous dimension is mixed directly into the new one.
+    #[default]
+    Crossfade,
+    /// The previous dimension fades out to `color`, then the new dimension fades in from it.
+    FadeThrough { color: Color },
+}
+
+impl TransitionKind {
+    /// Fades through black.
+    pub const FADE_THROUGH_BLACK: Self = Self::FadeThrough {
+        color: Color::BLACK,
+    };
+}
+
+/// The phase of a running [`DimensionTransition`], with its own progress from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransitionPhase {
+    /// The previous dimension fades out to the fade color.
+    Out(f32),
+    /// The new dimension fades in, from the fade color or directly from the previous dimension.
+    In(f32),
+}
+
+/// Starts a transition when the selected dimension changes, and advances the running ones.
+///
+/// Switching during a transition chains a new one, from the dimension which was selected.
+/// The oldest transitions are dropped past `MAX_TEXTURE_COUNT`, or once a later one is over.
+/// Peeking with `PeekDimension::crossfade` transitions like switching.
+pub fn update_transitions(
+    time: Res<Time>,
+    mut transitions: Query<(&Dimensions, Option<&PeekDimension>, &mut DimensionTransition)>,
+) {
+    for (dimensions, peek, mut transition) in transitions.iter_mut() {
+        let peek = peek.filter(|peek| peek.crossfade);
+        let displayed = PeekDimension::displayed(peek, dimensions);
+        let last_selected = transition.last_selected.replace(displayed);
+        if let Some(last_selected) = last_selected.filter(|last| *last != displayed) {
+            transition.sources.push((last_selected, 0.0));
+            let excess = transition.sources.len().saturating_sub(MAX_TEXTURE_COUNT);
+            transition.sources.drain(..excess);
+            continue;
+        }
+        let step = time.delta_seconds() / transition.duration.max(f32::EPSILON);
+        for (_, progress) in transition.sources.iter_mut() {
+            *progress += step;
+        }
+        // A finished transition fully covers the previous ones.
+        if let Some(finished) = transition
+            .sources
+            .iter()
+            .rposition(|(_, progress)| *progress >= 1.0)
+        {
+            transition.sources.drain(..=finished);
+        }
+    }
+}
+
+/// Flashes the screen with `color` when the selected dimension of the `Dimensions` on the same entity changes.
+///
+/// The flash is added over the composite, so it doesn't interfere with a [`DimensionTransition`] running
+/// at the same time. Its intensity rises quickly then falls slowly, see [`FlashOnSwitch::intensity`].
+#[derive(Component, Clone, Debug, Reflect, FromReflect)]
+#[reflect(Component)]
+pub struct FlashOnSwitch {
+    /// The color of the flash, its alpha scales the intensity.
+    pub color: Color,
+    /// Seconds a flash lasts.
+    pub duration: f32,
+    /// Seconds since the running flash started, `None` when no flash is running.
+    elapsed: Option<f32>,
+    /// The selected dimension on the previous update, to detect switches.
+    last_selected: Option<u32>,
+}
+
+impl Default for FlashOnSwitch {
+    fn default() -> Self {
+        Self::new(Color::WHITE, 0.3)
+    }
+}
+
+impl FlashOnSwitch {
+    /// Fraction of the duration the intensity takes to rise, it then falls for the rest of it.
+    const RISE: f32 = 0.1;
+
+    /// A flash of `color` lasting `duration` seconds.
+    pub fn new(color: Color, duration: f32) -> Self {
+        Self {
+            color,
+            duration,
+            elapsed: None,
+            last_selected: None,
+        }
+    }
+
+    /// The intensity of the running flash, from 0 to 1, 0 when no flash is running.
+    ///
+    /// It rises linearly during the first tenth of the duration, then falls quadratically to 0.
+    pub fn intensity(&self) -> f32 {
+        let Some(elapsed) = self.elapsed else {
+            return 0.0;
+        };
+        let t = (elapsed / self.duration.max(f32::EPSILON)).clamp(0.0, 1.0);
+        if t < Self::RISE {
+            t / Self::RISE
+        } else {
+            (1.0 - (t - Self::RISE) / (1.0 - Self::RISE)).powi(2)
+        }
+    }
+}
+
+/// Starts a flash when the selected dimension changes, and advances the running ones.
+///
+/// Switching during a flash restarts it.
+pub fn update_flashes(time: Res<Time>, mut flashes: Query<(&Dimensions, &mut FlashOnSwitch)>) {
+    for (dimensions, mut flash) in flashes.iter_mut() {
+        let last_selected = flash.last_selected.replace(dimensions.selected);
+        if last_selected.is_some_and(|last| last != dimensions.selected) {
+            flash.elapsed = Some(0.0);
+            continue;
+        }
+        let Some(elapsed) = flash.elapsed else {
+            continue;
+        };
+        let elapsed = elapsed + time.delta_seconds();
+        flash.elapsed = (elapsed < flash.duration).then_some(elapsed);
+    }
+}
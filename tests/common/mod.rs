@@ -0,0 +1,108 @@
+//! A headless app rendering the post process offscreen, for the tests needing a GPU.
+#![allow(dead_code)]
+
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    ecs::system::SystemState,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        render_resource::{Extent3d, TextureFormat},
+        view::RenderLayers,
+    },
+    tasks::block_on,
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_post_process_2_input_image::prelude::*;
+use bevy_post_process_2_input_image::{create_render_target, DIMENSION_CAMERA_ORDER};
+
+/// The size of the dimensions and of the rendered composite, small to keep the tests fast.
+pub const SIZE: Extent3d = Extent3d {
+    width: 64,
+    height: 64,
+    depth_or_array_layers: 1,
+};
+
+/// Whether a GPU adapter is available: without one, e.g. on CI, the tests rendering are skipped.
+pub fn has_adapter() -> bool {
+    let instance = wgpu::Instance::default();
+    block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default())).is_some()
+}
+
+/// An app rendering without a window, with the post process of `plugin`.
+pub fn headless_app(plugin: PostProcessPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugin(plugin);
+    app.finish();
+    app.cleanup();
+    app
+}
+
+/// Runs frames until the pipelines queued by the latest changes are compiled, they compile in the background.
+pub fn settle(app: &mut App) {
+    for _ in 0..30 {
+        app.update();
+    }
+}
+
+/// Spawns two dimensions cleared to red and blue, and the post process camera compositing them,
+/// rendering into an image. Returns the post process camera.
+pub fn spawn_two_dimensions(app: &mut App) -> Entity {
+    let mut state = SystemState::<(Commands, ResMut<Assets<Image>>)>::new(&mut app.world);
+    let (mut commands, mut images) = state.get_mut(&mut app.world);
+    let dimensions = [Color::RED, Color::BLUE]
+        .into_iter()
+        .enumerate()
+        .map(|(index, color)| {
+            let (image, _) = create_camera(
+                SIZE,
+                &mut images,
+                &mut commands,
+                RenderLayers::layer(index as u8 + 1),
+                ClearColorConfig::Custom(color),
+                DIMENSION_CAMERA_ORDER,
+                OrthographicProjection::default(),
+            );
+            image
+        })
+        .collect::<Vec<_>>();
+    let target = create_render_target(SIZE, &mut images);
+    let camera = commands
+        .spawn((
+            Camera2dBundle {
+                camera: Camera {
+                    target: RenderTarget::Image(target),
+                    ..default()
+                },
+                ..default()
+            },
+            Dimensions::from_handles(dimensions),
+            PostProcessCamera,
+        ))
+        .id();
+    state.apply(&mut app.world);
+    camera
+}
+
+/// The color of the pixel at `x`, `y` of `snapshot`, as `[r, g, b, a]` bytes.
+pub fn pixel(snapshot: &DimensionSnapshot, x: u32, y: u32) -> [u8; 4] {
+    let start = ((y * snapshot.size.x + x) * 4) as usize;
+    let mut pixel: [u8; 4] = snapshot.data[start..start + 4].try_into().unwrap();
+    if matches!(
+        snapshot.format,
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb
+    ) {
+        pixel.swap(0, 2);
+    }
+    pixel
+}
@@ -0,0 +1,28 @@
+mod common;
+
+use bevy_post_process_2_input_image::{render_composite_offscreen, PostProcessPlugin};
+
+#[test]
+fn custom_composite_shader_replaces_the_default() {
+    if !common::has_adapter() {
+        eprintln!("No GPU adapter, skipped");
+        return;
+    }
+    let mut app = common::headless_app(PostProcessPlugin {
+        // Relative to the `assets` folder.
+        composite_shader: Some("../tests/shaders/first_dimension.wgsl"),
+        ..Default::default()
+    });
+    let camera = common::spawn_two_dimensions(&mut app);
+    common::settle(&mut app);
+
+    let snapshot =
+        render_composite_offscreen(&mut app, camera, common::SIZE).expect("nothing rendered");
+    // The default composite would show the second dimension, blue, the replacement only the first one: red.
+    let [r, g, b, _] = common::pixel(&snapshot, 32, 32);
+    assert!(
+        r > g.saturating_add(100) && r > b.saturating_add(100),
+        "the composite isn't red: {:?}",
+        [r, g, b]
+    );
+}
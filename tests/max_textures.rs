@@ -0,0 +1,29 @@
+mod common;
+
+use bevy_post_process_2_input_image::{
+    render_composite_offscreen, PostProcessConfig, PostProcessPlugin,
+};
+
+#[test]
+fn changing_max_textures_rebuilds_the_pipeline() {
+    if !common::has_adapter() {
+        eprintln!("No GPU adapter, skipped");
+        return;
+    }
+    let mut app = common::headless_app(PostProcessPlugin::default());
+    let camera = common::spawn_two_dimensions(&mut app);
+
+    for max_textures in [2, 4, 3] {
+        app.world.resource_mut::<PostProcessConfig>().max_textures = max_textures;
+        common::settle(&mut app);
+        let snapshot = render_composite_offscreen(&mut app, camera, common::SIZE)
+            .unwrap_or_else(|| panic!("nothing rendered with {max_textures} textures"));
+        // The default composite shows the second dimension where it isn't black: blue.
+        let [r, g, b, _] = common::pixel(&snapshot, 32, 32);
+        assert!(
+            b > r.saturating_add(100) && b > g.saturating_add(100),
+            "the composite isn't blue with {max_textures} textures: {:?}",
+            [r, g, b]
+        );
+    }
+}
@@ -0,0 +1,11 @@
+#define_import_path bevy_post_process_2_input_image::composite
+
+// Only shows the selected dimension, without the default wobble, see `tests/composite_shader.rs`.
+
+fn composite_offset(slot: u32, uv: vec2<f32>, time: f32) -> vec2<f32> {
+    return vec2(0.0);
+}
+
+fn composite_background(colors: array<vec4<f32>, 4>, depths: array<f32, 4>, count: u32, depth_compositing: bool) -> vec4<f32> {
+    return colors[0];
+}
@@ -0,0 +1,165 @@
+//! Renders the two dimensions of `common::spawn_two_dimensions` with various settings,
+//! and compares the composite to the reference images in `tests/snapshots`.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write the references again after an intended change of the shader,
+//! and review them before committing. A new test only needs a name and its settings: its first run
+//! writes the missing reference and fails, until the reference is reviewed and committed.
+mod common;
+
+use std::path::Path;
+
+use bevy::{prelude::*, render::camera::RenderTarget};
+use bevy_post_process_2_input_image::{
+    CompositeLayout, CompositeLoad, DimensionSnapshot, Dimensions, PostProcessPlugin,
+    PostProcessSettings, ReadbackRequest,
+};
+
+/// How much a channel may differ from the reference, GPUs don't all round the same way.
+const CHANNEL_TOLERANCE: u8 = 3;
+/// How many pixels may differ more than `CHANNEL_TOLERANCE`, for the edges rasterized differently.
+const MAX_DIFFERING_PIXELS: usize = 16;
+
+/// Renders the composite of the two dimensions, with `settings` and the dimension at `selected` shown.
+///
+/// Returns `None` without a GPU adapter, the test is then skipped.
+fn render(settings: PostProcessSettings, selected: u32) -> Option<DimensionSnapshot> {
+    if !common::has_adapter() {
+        eprintln!("No GPU adapter, skipped");
+        return None;
+    }
+    let mut app = common::headless_app(PostProcessPlugin::default());
+    let camera = common::spawn_two_dimensions(&mut app);
+    app.world.entity_mut(camera).insert(PostProcessSettings {
+        // The animated effects would make every run different.
+        time_scale: 0.0,
+        // Where the composite is transparent, the main pass would show depending on the `CompositeBlend`.
+        composite_load: CompositeLoad::Clear(Color::NONE),
+        ..settings
+    });
+    app.world.get_mut::<Dimensions>(camera).unwrap().selected = selected;
+    common::settle(&mut app);
+
+    let RenderTarget::Image(target) = app.world.get::<Camera>(camera).unwrap().target.clone()
+    else {
+        unreachable!("the camera renders into an image");
+    };
+    app.world
+        .resource_mut::<Events<ReadbackRequest>>()
+        .send(ReadbackRequest { image: target });
+    // The pixels are read back a few frames later.
+    for _ in 0..10 {
+        app.update();
+        let snapshot = app
+            .world
+            .resource_mut::<Events<DimensionSnapshot>>()
+            .drain()
+            .last();
+        if snapshot.is_some() {
+            return snapshot;
+        }
+    }
+    panic!("the composite wasn't read back");
+}
+
+/// Compares `snapshot` to the reference `tests/snapshots/{name}.png`,
+/// writing the reference instead when it is missing or `UPDATE_SNAPSHOTS` is set.
+fn assert_snapshot(name: &str, snapshot: &DimensionSnapshot) {
+    let mut rendered = image::RgbaImage::new(snapshot.size.x, snapshot.size.y);
+    for (x, y, pixel) in rendered.enumerate_pixels_mut() {
+        *pixel = image::Rgba(common::pixel(snapshot, x, y));
+    }
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.png"));
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if update || !path.exists() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        rendered.save(&path).unwrap();
+        // A reference written by the test itself would always pass, it must be reviewed first.
+        assert!(
+            update,
+            "{} was missing and has been written, review it before committing it",
+            path.display()
+        );
+        return;
+    }
+
+    let reference = image::open(&path)
+        .unwrap_or_else(|error| panic!("can't read {}: {error}", path.display()))
+        .into_rgba8();
+    assert_eq!(
+        reference.dimensions(),
+        rendered.dimensions(),
+        "{name}: the size changed"
+    );
+    let differing = reference
+        .pixels()
+        .zip(rendered.pixels())
+        .filter(|(expected, actual)| {
+            expected
+                .0
+                .iter()
+                .zip(actual.0)
+                .any(|(expected, actual)| expected.abs_diff(actual) > CHANNEL_TOLERANCE)
+        })
+        .count();
+    if differing > MAX_DIFFERING_PIXELS {
+        let actual = path.with_extension("actual.png");
+        rendered.save(&actual).unwrap();
+        panic!(
+            "{name}: {differing} pixels differ from {}, the rendered image is in {}",
+            path.display(),
+            actual.display()
+        );
+    }
+}
+
+#[test]
+fn selection() {
+    if let Some(snapshot) = render(PostProcessSettings::default(), 0) {
+        assert_snapshot("selection", &snapshot);
+    }
+}
+
+#[test]
+fn second_selected() {
+    if let Some(snapshot) = render(PostProcessSettings::default(), 1) {
+        assert_snapshot("second_selected", &snapshot);
+    }
+}
+
+#[test]
+fn grid() {
+    let settings = PostProcessSettings {
+        layout: CompositeLayout::Grid { cols: 2 },
+        ..default()
+    };
+    if let Some(snapshot) = render(settings, 0) {
+        assert_snapshot("grid", &snapshot);
+    }
+}
+
+#[test]
+fn vignette() {
+    let settings = PostProcessSettings {
+        vignette_strength: 1.0,
+        ..default()
+    };
+    if let Some(snapshot) = render(settings, 0) {
+        assert_snapshot("vignette", &snapshot);
+    }
+}
+
+#[test]
+fn color_grading() {
+    let settings = PostProcessSettings {
+        brightness: 0.5,
+        contrast: 1.5,
+        gamma: 2.2,
+        ..default()
+    };
+    if let Some(snapshot) = render(settings, 0) {
+        assert_snapshot("color_grading", &snapshot);
+    }
+}